@@ -0,0 +1,62 @@
+use crate::{APIResponse, ImagePigError, Request};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// collapses byte-identical endpoint+payload combinations, returning the unique
+// requests and, for every original request, the index of its unique one
+pub(crate) fn deduplicate(requests: Vec<Request>) -> (Vec<Request>, Vec<usize>) {
+    let mut seen: HashMap<(String, String), usize> = HashMap::new();
+    let mut unique = Vec::new();
+    let mut slots = Vec::with_capacity(requests.len());
+
+    for request in requests {
+        let key = (
+            request.endpoint().to_string(),
+            serde_json::Value::Object(request.payload().clone()).to_string(),
+        );
+        let slot = *seen.entry(key).or_insert_with(|| {
+            unique.push(request);
+            unique.len() - 1
+        });
+        slots.push(slot);
+    }
+
+    (unique, slots)
+}
+
+// hands every original request the result of its unique one; an error that
+// several requests share is wrapped in ImagePigError::Shared
+pub(crate) fn fan_out(
+    results: Vec<Result<APIResponse, ImagePigError>>,
+    slots: &[usize],
+) -> Vec<Result<APIResponse, ImagePigError>> {
+    let mut last_use = vec![0; results.len()];
+    for (position, slot) in slots.iter().enumerate() {
+        last_use[*slot] = position;
+    }
+
+    let mut results: Vec<_> = results
+        .into_iter()
+        .map(|result| Some(result.map_err(Arc::new)))
+        .collect();
+
+    let fanned: Vec<_> = slots
+        .iter()
+        .enumerate()
+        .map(|(position, slot)| {
+            if last_use[*slot] == position {
+                results[*slot].take()
+            } else {
+                results[*slot].clone()
+            }
+        })
+        .collect();
+
+    fanned
+        .into_iter()
+        .flatten()
+        .map(|result| {
+            result.map_err(|error| Arc::try_unwrap(error).unwrap_or_else(ImagePigError::Shared))
+        })
+        .collect()
+}
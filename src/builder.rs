@@ -0,0 +1,189 @@
+use crate::{GenerationRequest, Image, ImagePigError, Params, Request};
+use std::marker::PhantomData;
+use std::num::NonZeroU32;
+
+// typestate markers: build() only exists once every required piece is set
+#[derive(Debug, Clone, Copy)]
+pub struct Missing;
+#[derive(Debug, Clone, Copy)]
+pub struct Ready;
+
+/// Builds a `replace` request, `build()` and `send()` need `select()` first:
+///
+/// ```
+/// # use imagepig::ReplaceBuilder;
+/// let request = ReplaceBuilder::new("https://example.com/pig.jpeg", "a crown")
+///     .unwrap()
+///     .select("the hat")
+///     .build();
+/// ```
+///
+/// ```compile_fail,E0599
+/// # use imagepig::ReplaceBuilder;
+/// let request = ReplaceBuilder::new("https://example.com/pig.jpeg", "a crown")
+///     .unwrap()
+///     .build();
+/// ```
+///
+/// ```compile_fail,E0277
+/// # use imagepig::{ImagePig, ReplaceBuilder};
+/// # async fn run(imagepig: ImagePig) {
+/// let builder = ReplaceBuilder::new("https://example.com/pig.jpeg", "a crown").unwrap();
+/// let result = imagepig.send(&builder).await;
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReplaceBuilder<Select = Missing> {
+    request: Request,
+    state: PhantomData<Select>,
+}
+
+impl ReplaceBuilder<Missing> {
+    pub fn new<T: Image>(image: T, positive_prompt: &str) -> Result<Self, ImagePigError> {
+        let mut request = Request::new("replace", Params::new());
+        image.prepare_image("image", request.payload_mut())?;
+        request.insert("positive_prompt", positive_prompt);
+        request.insert("negative_prompt", "");
+        Ok(Self {
+            request,
+            state: PhantomData,
+        })
+    }
+
+    // what to replace in the image, e.g. "the hat"
+    pub fn select(mut self, select_prompt: &str) -> ReplaceBuilder<Ready> {
+        self.request.insert("select_prompt", select_prompt);
+        ReplaceBuilder {
+            request: self.request,
+            state: PhantomData,
+        }
+    }
+}
+
+impl<Select> ReplaceBuilder<Select> {
+    pub fn negative_prompt(mut self, negative_prompt: &str) -> Self {
+        self.request.insert("negative_prompt", negative_prompt);
+        self
+    }
+
+    pub fn params(mut self, params: Params) -> Self {
+        self.request.payload_mut().extend(params.into_map());
+        self
+    }
+}
+
+impl ReplaceBuilder<Ready> {
+    pub fn build(self) -> Request {
+        self.request
+    }
+}
+
+impl GenerationRequest for ReplaceBuilder<Ready> {
+    fn endpoint(&self) -> &str {
+        self.request.endpoint()
+    }
+
+    fn params(&self) -> Result<Params, ImagePigError> {
+        self.request.params()
+    }
+}
+
+/// Builds an `outpaint` request, `build()` and `send()` need at least one side to expand:
+///
+/// ```
+/// # use imagepig::OutpaintBuilder;
+/// # use std::num::NonZeroU32;
+/// let request = OutpaintBuilder::new("https://example.com/pig.jpeg", "a farm")
+///     .unwrap()
+///     .left(NonZeroU32::new(256).unwrap())
+///     .build();
+/// ```
+///
+/// ```compile_fail,E0599
+/// # use imagepig::OutpaintBuilder;
+/// let request = OutpaintBuilder::new("https://example.com/pig.jpeg", "a farm")
+///     .unwrap()
+///     .build();
+/// ```
+///
+/// ```compile_fail,E0277
+/// # use imagepig::{ImagePig, OutpaintBuilder};
+/// # async fn run(imagepig: ImagePig) {
+/// let builder = OutpaintBuilder::new("https://example.com/pig.jpeg", "a farm").unwrap();
+/// let result = imagepig.send(&builder).await;
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OutpaintBuilder<Sides = Missing> {
+    request: Request,
+    state: PhantomData<Sides>,
+}
+
+impl OutpaintBuilder<Missing> {
+    pub fn new<T: Image>(image: T, positive_prompt: &str) -> Result<Self, ImagePigError> {
+        let mut request = Request::new("outpaint", Params::new());
+        image.prepare_image("image", request.payload_mut())?;
+        request.insert("positive_prompt", positive_prompt);
+        request.insert("negative_prompt", "");
+        for side in ["top", "right", "bottom", "left"] {
+            request.insert(side, 0);
+        }
+        Ok(Self {
+            request,
+            state: PhantomData,
+        })
+    }
+}
+
+impl<Sides> OutpaintBuilder<Sides> {
+    // a zero side would not expand anything, so it cannot mark the builder ready
+    fn expand(mut self, side: &str, pixels: NonZeroU32) -> OutpaintBuilder<Ready> {
+        self.request.insert(side, pixels.get());
+        OutpaintBuilder {
+            request: self.request,
+            state: PhantomData,
+        }
+    }
+
+    pub fn top(self, pixels: NonZeroU32) -> OutpaintBuilder<Ready> {
+        self.expand("top", pixels)
+    }
+
+    pub fn right(self, pixels: NonZeroU32) -> OutpaintBuilder<Ready> {
+        self.expand("right", pixels)
+    }
+
+    pub fn bottom(self, pixels: NonZeroU32) -> OutpaintBuilder<Ready> {
+        self.expand("bottom", pixels)
+    }
+
+    pub fn left(self, pixels: NonZeroU32) -> OutpaintBuilder<Ready> {
+        self.expand("left", pixels)
+    }
+
+    pub fn negative_prompt(mut self, negative_prompt: &str) -> Self {
+        self.request.insert("negative_prompt", negative_prompt);
+        self
+    }
+
+    pub fn params(mut self, params: Params) -> Self {
+        self.request.payload_mut().extend(params.into_map());
+        self
+    }
+}
+
+impl OutpaintBuilder<Ready> {
+    pub fn build(self) -> Request {
+        self.request
+    }
+}
+
+impl GenerationRequest for OutpaintBuilder<Ready> {
+    fn endpoint(&self) -> &str {
+        self.request.endpoint()
+    }
+
+    fn params(&self) -> Result<Params, ImagePigError> {
+        self.request.params()
+    }
+}
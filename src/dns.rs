@@ -0,0 +1,18 @@
+use reqwest::dns::{Name, Resolve, Resolving};
+use std::sync::Arc;
+
+// reqwest takes a concrete resolver type, so a user-supplied one is boxed behind this
+#[derive(Clone)]
+pub(crate) struct SharedResolver(pub Arc<dyn Resolve>);
+
+impl Resolve for SharedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        self.0.resolve(name)
+    }
+}
+
+impl std::fmt::Debug for SharedResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("SharedResolver")
+    }
+}
@@ -0,0 +1,214 @@
+use crate::progress::{ProgressCallback, Transfer};
+use crate::ImagePigError;
+use reqwest::{redirect, Client, Response};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+const DOWNLOAD_ATTEMPTS: u8 = 10;
+const DOWNLOAD_INTERRUPTION: u64 = 1;
+
+#[derive(Debug, Clone)]
+pub struct RedirectPolicy {
+    max_redirects: usize,
+    cross_origin: bool,
+    allowed_hosts: Vec<String>,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self {
+            max_redirects: 10,
+            cross_origin: true,
+            allowed_hosts: Vec::new(),
+        }
+    }
+}
+
+impl RedirectPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    pub fn cross_origin(mut self, allow: bool) -> Self {
+        self.cross_origin = allow;
+        self
+    }
+
+    // when set, both the initial URL and every redirect target must use one of these hosts
+    pub fn allow_host(mut self, host: &str) -> Self {
+        self.allowed_hosts.push(host.to_lowercase());
+        self
+    }
+
+    fn is_allowed(&self, url: &Url) -> bool {
+        self.allowed_hosts.is_empty()
+            || url
+                .host_str()
+                .is_some_and(|host| self.allowed_hosts.iter().any(|h| h == host))
+    }
+
+    pub(crate) fn build(self) -> redirect::Policy {
+        redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() > self.max_redirects {
+                attempt.error("too many redirects")
+            } else if !self.cross_origin && attempt.previous()[0].origin() != attempt.url().origin()
+            {
+                attempt.error("cross-origin redirect")
+            } else if !self.is_allowed(attempt.url()) {
+                attempt.error("redirect to a host that is not allowed")
+            } else {
+                attempt.follow()
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Downloader {
+    pub client: Client,
+    pub timeout: Option<Duration>,
+    pub user_agent: String,
+    pub redirects: Option<Arc<RedirectPolicy>>,
+    pub verify_content_type: bool,
+    pub progress: Option<ProgressCallback>,
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        Self {
+            client: Client::new(),
+            timeout: None,
+            user_agent: crate::USER_AGENT.to_string(),
+            redirects: None,
+            verify_content_type: false,
+            progress: None,
+        }
+    }
+}
+
+impl Downloader {
+    pub async fn fetch(
+        &self,
+        url: &str,
+        mime_type: Option<&str>,
+    ) -> Result<Vec<u8>, ImagePigError> {
+        let mut data = Vec::new();
+        self.fetch_with(url, mime_type, |chunk| {
+            data.extend_from_slice(chunk);
+            Ok(())
+        })
+        .await?;
+        Ok(data)
+    }
+
+    // hands the body over chunk by chunk so callers can write it out without buffering it whole
+    pub async fn fetch_with(
+        &self,
+        url: &str,
+        mime_type: Option<&str>,
+        on_chunk: impl FnMut(&[u8]) -> Result<(), ImagePigError>,
+    ) -> Result<(), ImagePigError> {
+        if let Some(policy) = &self.redirects {
+            let parsed = Url::parse(url).map_err(|_| ImagePigError::InvalidUrl(url.to_string()))?;
+            if !policy.is_allowed(&parsed) {
+                return Err(ImagePigError::ForbiddenHost(url.to_string()));
+            }
+        }
+
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.stream(url, mime_type, on_chunk))
+                .await
+                .map_err(|_| ImagePigError::DownloadTimeout(timeout))?,
+            None => self.stream(url, mime_type, on_chunk).await,
+        }
+    }
+
+    async fn stream(
+        &self,
+        url: &str,
+        mime_type: Option<&str>,
+        mut on_chunk: impl FnMut(&[u8]) -> Result<(), ImagePigError>,
+    ) -> Result<(), ImagePigError> {
+        let mut response = self.poll(url, mime_type).await?;
+        let total = response.content_length();
+        let mut received = 0;
+
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(ImagePigError::from_reqwest)?
+        {
+            on_chunk(&chunk)?;
+
+            if let Some(progress) = &self.progress {
+                received += chunk.len() as u64;
+                progress.report(Transfer::Download, received, total);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn poll(&self, url: &str, mime_type: Option<&str>) -> Result<Response, ImagePigError> {
+        let mut last_error = None;
+
+        for _ in 0..DOWNLOAD_ATTEMPTS {
+            let response = self
+                .client
+                .get(url)
+                .header("User-Agent", &self.user_agent)
+                .send()
+                .await;
+            match response {
+                Ok(resp) => {
+                    if resp.status().is_success() {
+                        if self.verify_content_type {
+                            check_content_type(&resp, mime_type)?;
+                        }
+
+                        return Ok(resp);
+                    }
+
+                    if resp.status().as_u16() == 404 {
+                        tokio::time::sleep(Duration::from_secs(DOWNLOAD_INTERRUPTION)).await;
+                    } else {
+                        break;
+                    }
+                }
+                // a redirect the policy refused would only be refused again
+                Err(e) if e.is_redirect() => return Err(ImagePigError::from_reqwest(e)),
+                Err(e) => last_error = Some(ImagePigError::from_reqwest(e)),
+            }
+        }
+
+        Err(last_error.unwrap_or(ImagePigError::MissingData))
+    }
+}
+
+fn check_content_type(response: &Response, expected: Option<&str>) -> Result<(), ImagePigError> {
+    let (Some(expected), Some(actual)) = (
+        expected,
+        response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok()),
+    ) else {
+        return Ok(());
+    };
+
+    let essence = actual.split(';').next().unwrap_or_default().trim();
+    if essence.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(ImagePigError::ContentTypeMismatch {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        })
+    }
+}
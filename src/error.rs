@@ -0,0 +1,209 @@
+use reqwest::Error as ReqwestError;
+use serde_json::Value;
+use std::error::Error as StdError;
+use std::io::ErrorKind as IoErrorKind;
+use thiserror::Error;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ImagePigError {
+    #[error("HTTP request failed: {0}")]
+    Network(#[source] ReqwestError),
+    #[error("HTTP request timed out")]
+    Timeout(#[source] ReqwestError),
+    #[error("Cannot decode response")]
+    Decode(#[source] BoxError),
+    #[error("API returned {status}: {message}")]
+    Api {
+        status: u16,
+        code: Option<String>,
+        message: String,
+    },
+    #[error("Cannot access {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Not a valid URL (local files are passed as a Path): {0}")]
+    InvalidUrl(String),
+    #[error("Invalid API URL, expected an http(s) URL with a host and no query, fragment or credentials: {0}")]
+    InvalidApiUrl(String),
+    #[error("Invalid endpoint: {0}")]
+    InvalidEndpoint(String),
+    #[error("Image download did not finish within {0:?}")]
+    DownloadTimeout(std::time::Duration),
+    #[error("Download host is not allowed: {0}")]
+    ForbiddenHost(String),
+    #[error("Downloaded {actual} but the API reported {expected}")]
+    ContentTypeMismatch { expected: String, actual: String },
+    #[error("Prompt has {length} characters, the limit is {limit}")]
+    PromptTooLong { length: usize, limit: usize },
+    #[error("Prompt weight must be a finite, non-negative number, got {0}")]
+    InvalidWeight(f32),
+    #[error("Unable to fetch image")]
+    MissingData,
+    #[error("Response contains {0} images, use images() or save_all()")]
+    MultipleImages(usize),
+    #[error("Cannot convert to {0} locally, request it from the API with Params::format()")]
+    UnsupportedFormat(String),
+    #[error("Cannot encode image")]
+    InvalidInput(#[source] BoxError),
+    #[error("Cannot serialize extra parameters")]
+    InvalidParams(#[source] BoxError),
+    // the failure of one request that gather() fanned out to its duplicates
+    #[error(transparent)]
+    Shared(std::sync::Arc<ImagePigError>),
+    #[error("Request rejected by on_request hook: {0}")]
+    RequestRejected(String),
+    #[error("Invalid workflow: {0}")]
+    InvalidWorkflow(#[source] BoxError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    Connection,
+    Dns,
+    Tls,
+    Timeout,
+    RateLimited,
+    Server,
+    Application,
+}
+
+impl ErrorKind {
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, ErrorKind::Application)
+    }
+}
+
+impl ImagePigError {
+    // the underlying error, looking through the wrapper gather() puts around failures
+    // that duplicate requests share
+    pub fn inner(&self) -> &ImagePigError {
+        match self {
+            ImagePigError::Shared(error) => error.inner(),
+            error => error,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self.inner() {
+            ImagePigError::Network(error) => classify(error),
+            ImagePigError::Timeout(_) | ImagePigError::DownloadTimeout(_) => ErrorKind::Timeout,
+            ImagePigError::Api { status: 429, .. } => ErrorKind::RateLimited,
+            ImagePigError::Api { status, .. } if *status >= 500 => ErrorKind::Server,
+            error @ ImagePigError::Api { message, .. }
+                if error.is_soft() && is_temporary(message) =>
+            {
+                ErrorKind::Server
+            }
+            _ => ErrorKind::Application,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.kind().is_retryable()
+    }
+
+    // an error object returned with a success status
+    pub fn is_soft(&self) -> bool {
+        matches!(self.inner(), ImagePigError::Api { status, .. } if (200..300).contains(status))
+    }
+
+    pub(crate) fn from_reqwest(error: ReqwestError) -> Self {
+        if error.is_timeout() {
+            ImagePigError::Timeout(error)
+        } else {
+            ImagePigError::Network(error)
+        }
+    }
+
+    pub(crate) fn decode(error: impl Into<BoxError>) -> Self {
+        ImagePigError::Decode(error.into())
+    }
+
+    pub(crate) fn invalid_input(error: impl Into<BoxError>) -> Self {
+        ImagePigError::InvalidInput(error.into())
+    }
+
+    pub(crate) fn invalid_params(error: impl Into<BoxError>) -> Self {
+        ImagePigError::InvalidParams(error.into())
+    }
+
+    #[cfg(feature = "download")]
+    pub(crate) fn invalid_workflow(error: impl Into<BoxError>) -> Self {
+        ImagePigError::InvalidWorkflow(error.into())
+    }
+
+    pub(crate) fn from_io(path: &str, source: std::io::Error) -> Self {
+        ImagePigError::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub(crate) fn from_api(status: u16, content: &Value) -> Self {
+        let field = |name: &str| content.get(name).and_then(|v| v.as_str()).map(String::from);
+        ImagePigError::Api {
+            status,
+            code: field("code"),
+            message: field("message")
+                .or_else(|| field("error"))
+                .unwrap_or_else(|| content.to_string()),
+        }
+    }
+}
+
+fn is_temporary(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "overload",
+        "temporar",
+        "busy",
+        "unavailable",
+        "try again",
+        "timed out",
+    ]
+    .iter()
+    .any(|hint| message.contains(hint))
+}
+
+// reqwest does not expose the failure cause, so walk the source chain; the
+// outer error is skipped as its message includes the URL, which may contain "ssl"
+fn classify(error: &ReqwestError) -> ErrorKind {
+    let mut source = error.source();
+
+    while let Some(current) = source {
+        if let Some(io) = current.downcast_ref::<std::io::Error>() {
+            if matches!(
+                io.kind(),
+                IoErrorKind::ConnectionReset
+                    | IoErrorKind::ConnectionRefused
+                    | IoErrorKind::ConnectionAborted
+                    | IoErrorKind::BrokenPipe
+                    | IoErrorKind::UnexpectedEof
+            ) {
+                return ErrorKind::Connection;
+            }
+        }
+
+        let message = current.to_string().to_lowercase();
+        if message.contains("dns error") || message.contains("failed to lookup address") {
+            return ErrorKind::Dns;
+        }
+        if message.contains("tls") || message.contains("ssl") || message.contains("handshake") {
+            return ErrorKind::Tls;
+        }
+
+        source = current.source();
+    }
+
+    if error.is_connect() {
+        ErrorKind::Connection
+    } else {
+        ErrorKind::Application
+    }
+}
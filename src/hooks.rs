@@ -0,0 +1,50 @@
+use crate::{APIResponse, ImagePigError};
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+type Payload = serde_json::Map<String, Value>;
+type RequestFn = dyn Fn(&str, &mut Payload) -> Result<(), String> + Send + Sync;
+type ResponseFn = dyn Fn(&str, &Result<APIResponse, ImagePigError>) + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct RequestHook(Arc<RequestFn>);
+
+impl RequestHook {
+    pub fn new(
+        hook: impl Fn(&str, &mut Payload) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(hook))
+    }
+
+    pub fn call(&self, endpoint: &str, payload: &mut Payload) -> Result<(), ImagePigError> {
+        (self.0)(endpoint, payload).map_err(ImagePigError::RequestRejected)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ResponseHook(Arc<ResponseFn>);
+
+impl ResponseHook {
+    pub fn new(
+        hook: impl Fn(&str, &Result<APIResponse, ImagePigError>) + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(hook))
+    }
+
+    pub fn call(&self, endpoint: &str, result: &Result<APIResponse, ImagePigError>) {
+        (self.0)(endpoint, result)
+    }
+}
+
+impl fmt::Debug for RequestHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("RequestHook")
+    }
+}
+
+impl fmt::Debug for ResponseHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ResponseHook")
+    }
+}
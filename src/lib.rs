@@ -1,72 +1,183 @@
+#[cfg(feature = "download")]
+use base64::prelude::BASE64_STANDARD;
+#[cfg(feature = "download")]
+use base64::read::DecoderReader;
 use base64::Engine;
-use chrono::{DateTime, Duration};
-use reqwest::{Client, Error as ReqwestError};
+use bytes::Bytes;
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Duration, FixedOffset};
+use reqwest::dns::Resolve;
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, CONTENT_TYPE};
+use reqwest::{Client, ClientBuilder, StatusCode};
 use serde::Deserialize;
 use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+#[cfg(feature = "download")]
 use std::fs::File;
+use std::future::Future;
+#[cfg(feature = "download")]
 use std::io::Write;
-use std::time::Duration as StdDuration;
-use thiserror::Error;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration as StdDuration, Instant};
+use tokio::task::JoinSet;
 use url::Url;
 
-const DOWNLOAD_ATTEMPTS: u8 = 10;
-const DOWNLOAD_INTERRUPTION: u64 = 1;
-
-#[derive(Debug, Error)]
-pub enum ImagePigError {
-    #[error("HTTP request failed: {0}")]
-    HttpError(ReqwestError),
-    #[error("Invalid URL: {0}")]
-    InvalidUrl(String),
-    #[error("Unexpected response")]
-    UnexpectedResponse,
-    #[error("Unable to fetch image")]
-    MissingData,
-    #[error("Cannot encode file to base64")]
-    InvalidInput,
-}
+mod batch;
+mod builder;
+mod dns;
+#[cfg(feature = "download")]
+mod download;
+mod error;
+mod hooks;
+mod journal;
+mod logging;
+#[cfg(feature = "download")]
+mod manifest;
+mod params;
+#[cfg(feature = "download")]
+mod pipeline;
+mod progress;
+mod prompt;
+mod rate_limit;
+mod request;
+#[cfg(feature = "download")]
+mod save;
+#[cfg(feature = "image")]
+mod similarity;
+mod stats;
+mod trace;
+#[cfg(feature = "download")]
+mod workflow;
+
+pub use builder::{Missing, OutpaintBuilder, Ready, ReplaceBuilder};
+use dns::SharedResolver;
+#[cfg(feature = "download")]
+use download::Downloader;
+#[cfg(feature = "download")]
+pub use download::RedirectPolicy;
+pub use error::{ErrorKind, ImagePigError};
+use hooks::{RequestHook, ResponseHook};
+#[cfg(feature = "download")]
+pub use manifest::{Manifest, ManifestEntry};
+pub use params::{OutpaintOptions, OutputFormat, Params};
+#[cfg(feature = "download")]
+pub use pipeline::{Pipeline, PipelineOutput};
+use progress::ProgressCallback;
+pub use progress::{Progress, Transfer};
+pub use prompt::Prompt;
+pub use rate_limit::RateLimit;
+use rate_limit::RateLimitTracker;
+pub use request::{GenerationRequest, Request};
+#[cfg(feature = "download")]
+pub use save::SaveAs;
+#[cfg(feature = "image")]
+pub use similarity::{dedup, find_duplicates, ssim, PerceptualHash};
+pub use stats::EndpointStats;
+use stats::Stats;
+#[cfg(feature = "download")]
+pub use workflow::Workflow;
 
-#[derive(Deserialize, Debug)]
+const USER_AGENT: &str = concat!("imagepig-rust/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Deserialize, Debug, Clone)]
 pub struct APIResponse {
     content: serde_json::Value,
+    #[serde(skip)]
+    endpoint: String,
+    #[serde(skip)]
+    status: StatusCode,
+    #[serde(skip)]
+    headers: HeaderMap,
+    #[cfg(feature = "download")]
+    #[serde(skip)]
+    downloader: Downloader,
 }
 
 impl APIResponse {
+    // "" for the default model
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    // the parsed JSON body, for fields without a dedicated accessor
+    pub fn content(&self) -> &Value {
+        &self.content
+    }
+
+    // responses with several images have to be read through images()
     pub async fn data(&self) -> Result<Vec<u8>, ImagePigError> {
+        self.single()?.single_data().await
+    }
+
+    async fn single_data(&self) -> Result<Vec<u8>, ImagePigError> {
         if let Some(data) = self.content.get("image_data") {
             if let Some(data_str) = data.as_str() {
                 return base64::prelude::BASE64_STANDARD
                     .decode(data_str)
-                    .map_err(|_| ImagePigError::UnexpectedResponse);
+                    .map_err(ImagePigError::decode);
             }
         }
 
+        #[cfg(feature = "download")]
         if let Some(url) = self.url() {
-            for _ in 0..DOWNLOAD_ATTEMPTS {
-                let response = Client::new()
-                    .get(url.to_string())
-                    .header("User-Agent", "Mozilla/5.0")
-                    .send()
-                    .await;
-                if let Ok(resp) = response {
-                    if resp.status().is_success() {
-                        return resp
-                            .bytes()
-                            .await
-                            .map(|b| b.to_vec())
-                            .map_err(ImagePigError::HttpError);
-                    }
+            return self
+                .downloader
+                .fetch(&url, self.mime_type().as_deref())
+                .await;
+        }
 
-                    if resp.status().as_u16() == 404 {
-                        tokio::time::sleep(StdDuration::from_secs(DOWNLOAD_INTERRUPTION)).await;
-                    } else {
-                        break;
-                    }
+        Err(ImagePigError::MissingData)
+    }
+
+    // one response per candidate image, each with its own data, URL and seed;
+    // a single-image response yields just itself. The API does not document
+    // multiple images yet, the "images" array of objects with the single-image
+    // fields is an assumed schema and may change once it does
+    pub fn images(&self) -> Vec<APIResponse> {
+        let Some(items) = self.content.get("images").and_then(Value::as_array) else {
+            return vec![self.clone()];
+        };
+
+        let mut shared = self.content.as_object().cloned().unwrap_or_default();
+        shared.remove("images");
+
+        items
+            .iter()
+            .filter_map(Value::as_object)
+            .map(|item| {
+                let mut content = shared.clone();
+                content.extend(item.clone());
+                APIResponse {
+                    content: Value::Object(content),
+                    ..self.clone()
                 }
-            }
+            })
+            .collect()
+    }
+
+    // the only image of the response, so single-image accessors never pick one silently
+    pub(crate) fn single(&self) -> Result<Cow<'_, APIResponse>, ImagePigError> {
+        if self.content.get("images").is_none() {
+            return Ok(Cow::Borrowed(self));
         }
 
-        Err(ImagePigError::MissingData)
+        let mut images = self.images();
+        match images.len() {
+            1 => Ok(Cow::Owned(images.remove(0))),
+            0 => Err(ImagePigError::MissingData),
+            count => Err(ImagePigError::MultipleImages(count)),
+        }
     }
 
     pub fn url(&self) -> Option<String> {
@@ -76,9 +187,7 @@ impl APIResponse {
     }
 
     pub fn seed(&self) -> Option<u64> {
-        self.content
-            .get("seed")
-            .and_then(|seed| seed.as_u64().map(|s| s as u64))
+        self.content.get("seed").and_then(|seed| seed.as_u64())
     }
 
     pub fn mime_type(&self) -> Option<String> {
@@ -87,6 +196,30 @@ impl APIResponse {
             .and_then(|mime| mime.as_str().map(|s| s.to_string()))
     }
 
+    #[cfg(feature = "chrono")]
+    // when the hosted image behind url() stops being available, if the API reports it
+    pub fn expires_at(&self) -> Option<DateTime<FixedOffset>> {
+        let expires_at = self.content.get("expires_at")?.as_str()?;
+        DateTime::parse_from_rfc3339(expires_at).ok()
+    }
+
+    // decoded size of inline image data, without downloading hosted images
+    pub fn size(&self) -> Option<usize> {
+        let data = self.content.get("image_data")?.as_str()?;
+        let padding = data.bytes().rev().take_while(|b| *b == b'=').count();
+        // malformed base64 has no meaningful size
+        if data.len() % 4 != 0 || padding > 2 {
+            return None;
+        }
+        (data.len() / 4 * 3).checked_sub(padding)
+    }
+
+    // one line for logs, e.g. "flux: seed 42, image/jpeg, 3.2s, https://..."
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+
+    #[cfg(feature = "chrono")]
     pub fn duration(&self) -> Option<Duration> {
         if let (Some(started), Some(completed)) = (
             self.content.get("started_at"),
@@ -100,16 +233,173 @@ impl APIResponse {
         None
     }
 
-    pub async fn save(&self, path: &str) -> Result<(), ImagePigError> {
+    #[cfg(feature = "image")]
+    pub async fn image(&self) -> Result<image::DynamicImage, ImagePigError> {
+        image::load_from_memory(&self.data().await?).map_err(ImagePigError::decode)
+    }
+
+    // decodes the result and re-encodes it, for formats the API cannot produce directly
+    #[cfg(all(feature = "image", feature = "download"))]
+    pub async fn save_as_format(
+        &self,
+        path: &str,
+        format: OutputFormat,
+    ) -> Result<(), ImagePigError> {
+        // AVIF would need a heavy encoder, it can still be requested from the API
+        let image_format = image::ImageFormat::from(format);
+        if !image_format.writing_enabled() {
+            return Err(ImagePigError::UnsupportedFormat(format.to_string()));
+        }
+
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        self.image()
+            .await?
+            .write_to(&mut buffer, image_format)
+            .map_err(ImagePigError::invalid_input)?;
+        std::fs::write(path, buffer.into_inner()).map_err(|e| ImagePigError::from_io(path, e))
+    }
+
+    // like save(), but rotates or flips the pixels per the EXIF orientation tag,
+    // re-encoding only when the image is not already upright
+    #[cfg(all(feature = "image", feature = "download"))]
+    pub async fn save_oriented(&self, path: &str) -> Result<(), ImagePigError> {
+        use image::ImageDecoder;
+
         let data = self.data().await?;
-        let mut file = File::create(path).map_err(|_| ImagePigError::UnexpectedResponse)?;
-        file.write_all(&data)
-            .map_err(|_| ImagePigError::UnexpectedResponse)?;
-        Ok(())
+        let reader = image::ImageReader::new(std::io::Cursor::new(&data))
+            .with_guessed_format()
+            .map_err(ImagePigError::decode)?;
+        let format = reader.format().ok_or(ImagePigError::MissingData)?;
+        let mut decoder = reader.into_decoder().map_err(ImagePigError::decode)?;
+        let orientation = decoder.orientation().map_err(ImagePigError::decode)?;
+
+        if orientation == image::metadata::Orientation::NoTransforms {
+            return std::fs::write(path, &data).map_err(|e| ImagePigError::from_io(path, e));
+        }
+
+        let mut image =
+            image::DynamicImage::from_decoder(decoder).map_err(ImagePigError::decode)?;
+        image.apply_orientation(orientation);
+        image
+            .save_with_format(path, format)
+            .map_err(ImagePigError::invalid_input)
+    }
+
+    // responses with several images have to be saved with save_all()
+    #[cfg(feature = "download")]
+    pub async fn save(&self, path: &str) -> Result<(), ImagePigError> {
+        self.single()?.save_single(path).await
+    }
+
+    // saves every image, substituting {index} in the path (or appending it to the file name)
+    #[cfg(feature = "download")]
+    pub async fn save_all(&self, path: &str) -> Result<Vec<String>, ImagePigError> {
+        let mut paths = Vec::new();
+        for (index, image) in self.images().iter().enumerate() {
+            let path = indexed_path(path, index);
+            image.save_single(&path).await?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    // streams the image into the file so the decoded bytes are never held in memory as a whole;
+    // the base64 payload of an inline response still is, as the response is parsed whole
+    #[cfg(feature = "download")]
+    async fn save_single(&self, path: &str) -> Result<(), ImagePigError> {
+        let inline = self
+            .content
+            .get("image_data")
+            .and_then(|data| data.as_str());
+        let url = self.url();
+
+        if inline.is_none() && url.is_none() {
+            return Err(ImagePigError::MissingData);
+        }
+
+        let mut file = File::create(path).map_err(|e| ImagePigError::from_io(path, e))?;
+
+        let result = match (inline, url) {
+            (Some(data), _) => {
+                let mut decoder = DecoderReader::new(data.as_bytes(), &BASE64_STANDARD);
+                match std::io::copy(&mut decoder, &mut file) {
+                    Ok(_) => Ok(()),
+                    Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                        Err(ImagePigError::decode(e))
+                    }
+                    Err(e) => Err(ImagePigError::from_io(path, e)),
+                }
+            }
+            (None, Some(url)) => {
+                self.downloader
+                    .fetch_with(&url, self.mime_type().as_deref(), |chunk| {
+                        file.write_all(chunk)
+                            .map_err(|e| ImagePigError::from_io(path, e))
+                    })
+                    .await
+            }
+            (None, None) => unreachable!(),
+        };
+
+        if result.is_err() {
+            drop(file);
+            let _ = std::fs::remove_file(path);
+        }
+        result
+    }
+}
+
+impl std::fmt::Display for APIResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(seed) = self.seed() {
+            parts.push(format!("seed {}", seed));
+        }
+        if let Some(mime_type) = self.mime_type() {
+            parts.push(mime_type);
+        }
+        if let Some(size) = self.size() {
+            parts.push(format!("{} bytes", size));
+        }
+        #[cfg(feature = "chrono")]
+        if let Some(duration) = self.duration() {
+            parts.push(format!(
+                "{:.1}s",
+                duration.num_milliseconds() as f64 / 1000.0
+            ));
+        }
+        if let Some(url) = self.url() {
+            parts.push(url);
+        }
+
+        let endpoint = match self.endpoint.as_str() {
+            "" => "default",
+            endpoint => endpoint,
+        };
+        write!(f, "{}: {}", endpoint, parts.join(", "))
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuthScheme {
+    // the "Api-Key" header the API documents
+    #[default]
+    ApiKey,
+    // "Authorization: Bearer <key>", e.g. behind gateways that expect standard auth
+    Bearer,
+}
+
+impl AuthScheme {
+    fn header(&self, api_key: &str) -> (&'static str, String) {
+        match self {
+            AuthScheme::ApiKey => ("Api-Key", api_key.to_string()),
+            AuthScheme::Bearer => ("Authorization", format!("Bearer {}", api_key)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Proportion {
     Landscape,
     Portrait,
@@ -123,7 +413,7 @@ impl std::fmt::Display for Proportion {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum UpscalingFactor {
     Two = 2,
     Four = 4,
@@ -138,25 +428,49 @@ pub trait Image {
     ) -> Result<(), ImagePigError>;
 }
 
-impl Image for &str {
+// a string is only ever treated as a URL, so inputs taken from users cannot
+// make the client read and upload local files; those are passed as a Path
+impl Image for str {
     fn prepare_image(
         &self,
         param_name: &str,
         params: &mut serde_json::Map<String, Value>,
     ) -> Result<(), ImagePigError> {
-        if Url::parse(self).is_ok() {
-            params.insert(
-                format!("{}_url", param_name),
-                serde_json::Value::from(self.to_string()),
-            );
-            return Ok(());
+        if Url::parse(self).is_err() {
+            return Err(ImagePigError::InvalidUrl(self.to_string()));
         }
 
-        return Err(ImagePigError::InvalidUrl(self.to_string()));
+        params.insert(
+            format!("{}_url", param_name),
+            serde_json::Value::from(self.to_string()),
+        );
+        Ok(())
     }
 }
 
-impl Image for Vec<u8> {
+impl Image for Path {
+    fn prepare_image(
+        &self,
+        param_name: &str,
+        params: &mut serde_json::Map<String, Value>,
+    ) -> Result<(), ImagePigError> {
+        let data = std::fs::read(self)
+            .map_err(|e| ImagePigError::from_io(&self.display().to_string(), e))?;
+        data.prepare_image(param_name, params)
+    }
+}
+
+impl Image for PathBuf {
+    fn prepare_image(
+        &self,
+        param_name: &str,
+        params: &mut serde_json::Map<String, Value>,
+    ) -> Result<(), ImagePigError> {
+        self.as_path().prepare_image(param_name, params)
+    }
+}
+
+impl Image for [u8] {
     fn prepare_image(
         &self,
         param_name: &str,
@@ -164,148 +478,786 @@ impl Image for Vec<u8> {
     ) -> Result<(), ImagePigError> {
         params.insert(
             format!("{}_data", param_name),
-            serde_json::Value::from(
-                base64::prelude::BASE64_STANDARD
-                    .decode(self)
-                    .map_err(|_| ImagePigError::InvalidInput)?,
-            ),
+            serde_json::Value::from(base64::prelude::BASE64_STANDARD.encode(self)),
         );
         Ok(())
     }
 }
 
-#[derive(Debug)]
+impl Image for String {
+    fn prepare_image(
+        &self,
+        param_name: &str,
+        params: &mut serde_json::Map<String, Value>,
+    ) -> Result<(), ImagePigError> {
+        self.as_str().prepare_image(param_name, params)
+    }
+}
+
+// lets mixed inputs be collected as Box<dyn Image> or passed as &dyn Image
+impl<T: Image + ?Sized> Image for &T {
+    fn prepare_image(
+        &self,
+        param_name: &str,
+        params: &mut serde_json::Map<String, Value>,
+    ) -> Result<(), ImagePigError> {
+        (**self).prepare_image(param_name, params)
+    }
+}
+
+impl<T: Image + ?Sized> Image for Box<T> {
+    fn prepare_image(
+        &self,
+        param_name: &str,
+        params: &mut serde_json::Map<String, Value>,
+    ) -> Result<(), ImagePigError> {
+        (**self).prepare_image(param_name, params)
+    }
+}
+
+impl Image for Vec<u8> {
+    fn prepare_image(
+        &self,
+        param_name: &str,
+        params: &mut serde_json::Map<String, Value>,
+    ) -> Result<(), ImagePigError> {
+        self.as_slice().prepare_image(param_name, params)
+    }
+}
+
+impl Image for Bytes {
+    fn prepare_image(
+        &self,
+        param_name: &str,
+        params: &mut serde_json::Map<String, Value>,
+    ) -> Result<(), ImagePigError> {
+        self.as_ref().prepare_image(param_name, params)
+    }
+}
+
+#[cfg(feature = "image")]
+impl Image for image::DynamicImage {
+    fn prepare_image(
+        &self,
+        param_name: &str,
+        params: &mut serde_json::Map<String, Value>,
+    ) -> Result<(), ImagePigError> {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        self.write_to(&mut buffer, image::ImageFormat::Png)
+            .map_err(ImagePigError::invalid_input)?;
+        buffer.into_inner().prepare_image(param_name, params)
+    }
+}
+
+// The API has no upload endpoint, so an image cannot be hosted once and
+// referenced by URL. Instead the input is resolved and encoded a single time
+// and the resulting parameter is reused for every request.
+#[derive(Debug, Clone)]
+pub struct PreparedImage {
+    suffix: &'static str,
+    value: Arc<Value>,
+}
+
+impl PreparedImage {
+    pub fn new<T: Image>(image: T) -> Result<Self, ImagePigError> {
+        let mut params = serde_json::Map::new();
+        image.prepare_image("image", &mut params)?;
+
+        for suffix in ["url", "data"] {
+            if let Some(value) = params.remove(&format!("image_{}", suffix)) {
+                return Ok(Self {
+                    suffix,
+                    value: Arc::new(value),
+                });
+            }
+        }
+
+        Err(ImagePigError::MissingData)
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        match self.suffix {
+            "url" => self.value.as_str(),
+            _ => None,
+        }
+    }
+}
+
+impl Image for PreparedImage {
+    fn prepare_image(
+        &self,
+        param_name: &str,
+        params: &mut serde_json::Map<String, Value>,
+    ) -> Result<(), ImagePigError> {
+        params.insert(
+            format!("{}_{}", param_name, self.suffix),
+            self.value.as_ref().clone(),
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ImagePig {
     api_key: String,
-    api_url: String,
+    auth_scheme: AuthScheme,
+    api_url: Url,
+    // header name and value
+    organization: Option<(String, String)>,
+    journal: Option<String>,
+    logging: bool,
+    trace_requests: bool,
+    proportion: Proportion,
+    upscaling_factor: UpscalingFactor,
+    user_agent: String,
+    soft_error_retries: u32,
+    deduplicate: bool,
+    timeouts: HashMap<String, StdDuration>,
+    dns_overrides: Vec<(String, SocketAddr)>,
+    dns_resolver: Option<SharedResolver>,
+    progress: Option<ProgressCallback>,
+    on_request: Option<RequestHook>,
+    on_response: Option<ResponseHook>,
     client: Client,
+    #[cfg(feature = "download")]
+    downloader: Downloader,
+    stats: Stats,
+    rate_limit: RateLimitTracker,
 }
 
 impl ImagePig {
-    pub fn new(api_key: String, api_url: Option<String>) -> Self {
+    pub fn new(api_key: String, api_url: Option<String>) -> Result<Self, ImagePigError> {
         let api_url = api_url.unwrap_or_else(|| "https://api.imagepig.com".to_string());
+
+        match Url::parse(&api_url) {
+            // a query or fragment could never prefix an endpoint URL, and credentials
+            // would be sent to every endpoint and show up in logs
+            Ok(mut url)
+                if matches!(url.scheme(), "http" | "https")
+                    && url.has_host()
+                    && url.query().is_none()
+                    && url.fragment().is_none()
+                    && url.username().is_empty()
+                    && url.password().is_none() =>
+            {
+                // endpoints are joined relative to the base, which only keeps its path with a trailing slash
+                if !url.path().ends_with('/') {
+                    url.set_path(&format!("{}/", url.path()));
+                }
+                Ok(Self::with_url(api_key, url))
+            }
+            _ => Err(ImagePigError::InvalidApiUrl(api_url)),
+        }
+    }
+
+    pub fn new_unchecked(api_key: String, api_url: Option<String>) -> Self {
+        Self::new(api_key, api_url).unwrap_or_else(|e| panic!("cannot create ImagePig: {}", e))
+    }
+
+    fn with_url(api_key: String, api_url: Url) -> Self {
+        let client = Client::new();
         Self {
             api_key,
+            auth_scheme: AuthScheme::default(),
             api_url,
-            client: Client::new(),
+            organization: None,
+            journal: None,
+            logging: false,
+            trace_requests: false,
+            proportion: Proportion::Landscape,
+            upscaling_factor: UpscalingFactor::Two,
+            user_agent: USER_AGENT.to_string(),
+            soft_error_retries: 2,
+            deduplicate: true,
+            timeouts: default_timeouts(),
+            dns_overrides: Vec::new(),
+            dns_resolver: None,
+            progress: None,
+            on_request: None,
+            on_response: None,
+            #[cfg(feature = "download")]
+            downloader: Downloader {
+                client: client.clone(),
+                timeout: None,
+                user_agent: USER_AGENT.to_string(),
+                redirects: None,
+                verify_content_type: false,
+                progress: None,
+            },
+            client,
+            stats: Stats::default(),
+            rate_limit: RateLimitTracker::default(),
+        }
+    }
+
+    pub fn auth_scheme(mut self, scheme: AuthScheme) -> Self {
+        self.auth_scheme = scheme;
+        self
+    }
+
+    // the API documents no organization header, so the name is whatever the
+    // gateway in front of it reads to attribute usage, e.g. "X-Team"
+    pub fn organization(mut self, header: String, organization: String) -> Self {
+        self.organization = Some((header, organization));
+        self
+    }
+
+    pub fn journal(mut self, path: String) -> Self {
+        self.journal = Some(path);
+        self
+    }
+
+    // payloads and responses are logged at debug level with image data elided
+    pub fn logging(mut self, enabled: bool) -> Self {
+        self.logging = enabled;
+        self
+    }
+
+    // generate a traceparent header for requests that do not carry one
+    pub fn trace_requests(mut self, enabled: bool) -> Self {
+        self.trace_requests = enabled;
+        self
+    }
+
+    pub fn default_proportion(mut self, proportion: Proportion) -> Self {
+        self.proportion = proportion;
+        self
+    }
+
+    pub fn default_upscaling_factor(mut self, factor: UpscalingFactor) -> Self {
+        self.upscaling_factor = factor;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: String) -> Self {
+        #[cfg(feature = "download")]
+        {
+            self.downloader.user_agent = user_agent.clone();
+        }
+        self.user_agent = user_agent;
+        self
+    }
+
+    #[cfg(feature = "download")]
+    pub fn download_timeout(mut self, timeout: StdDuration) -> Self {
+        self.downloader.timeout = Some(timeout);
+        self
+    }
+
+    #[cfg(feature = "download")]
+    pub fn download_redirects(mut self, policy: RedirectPolicy) -> Self {
+        self.downloader.redirects = Some(Arc::new(policy));
+        self.rebuild_clients();
+        self
+    }
+
+    // how many times a retryable error reported with a success status is retried
+    pub fn soft_error_retries(mut self, retries: u32) -> Self {
+        self.soft_error_retries = retries;
+        self
+    }
+
+    // gather() sends byte-identical requests once and hands every duplicate the same result;
+    // turn off to get separate generations for repeated requests without a seed
+    pub fn deduplicate_requests(mut self, enabled: bool) -> Self {
+        self.deduplicate = enabled;
+        self
+    }
+
+    // overrides the built-in timeout for one endpoint ("" is the default model)
+    pub fn endpoint_timeout(mut self, endpoint: &str, timeout: StdDuration) -> Self {
+        self.timeouts.insert(endpoint.to_string(), timeout);
+        self
+    }
+
+    // connect to a fixed address for a host, e.g. when the public hostname does not resolve
+    pub fn resolve(mut self, host: String, address: SocketAddr) -> Self {
+        self.dns_overrides.push((host, address));
+        self.rebuild_clients();
+        self
+    }
+
+    // used for every lookup not covered by resolve()
+    pub fn dns_resolver(mut self, resolver: Arc<dyn Resolve>) -> Self {
+        self.dns_resolver = Some(SharedResolver(resolver));
+        self.rebuild_clients();
+        self
+    }
+
+    fn client_builder(&self) -> ClientBuilder {
+        let mut builder = Client::builder();
+
+        for (host, address) in &self.dns_overrides {
+            builder = builder.resolve(host, *address);
+        }
+        if let Some(resolver) = &self.dns_resolver {
+            builder = builder.dns_resolver(Arc::new(resolver.clone()));
+        }
+
+        builder
+    }
+
+    fn rebuild_clients(&mut self) {
+        self.client = self
+            .client_builder()
+            .build()
+            .expect("cannot build HTTP client");
+
+        #[cfg(feature = "download")]
+        {
+            let mut builder = self.client_builder();
+            if let Some(policy) = &self.downloader.redirects {
+                builder = builder.redirect(policy.as_ref().clone().build());
+            }
+            self.downloader.client = builder.build().expect("cannot build HTTP client");
+        }
+    }
+
+    // called with the bytes transferred so far for API uploads and image downloads
+    pub fn on_progress(mut self, callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        let callback = ProgressCallback::new(callback);
+        #[cfg(feature = "download")]
+        {
+            self.downloader.progress = Some(callback.clone());
+        }
+        self.progress = Some(callback);
+        self
+    }
+
+    #[cfg(feature = "download")]
+    pub fn verify_content_type(mut self, verify: bool) -> Self {
+        self.downloader.verify_content_type = verify;
+        self
+    }
+
+    // may change the payload before it is sent, or return Err to cancel the request
+    pub fn on_request(
+        mut self,
+        hook: impl Fn(&str, &mut serde_json::Map<String, Value>) -> Result<(), String>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.on_request = Some(RequestHook::new(hook));
+        self
+    }
+
+    // called with the final result of every request, after soft error retries
+    pub fn on_response(
+        mut self,
+        hook: impl Fn(&str, &Result<APIResponse, ImagePigError>) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_response = Some(ResponseHook::new(hook));
+        self
+    }
+
+    pub fn stats(&self) -> HashMap<String, EndpointStats> {
+        self.stats.snapshot()
+    }
+
+    pub fn rate_limit_status(&self) -> Option<RateLimit> {
+        self.rate_limit.latest()
+    }
+
+    // opens pooled connections (DNS, TCP, TLS) ahead of the first generation,
+    // optionally also to the CDN host images are downloaded from
+    pub async fn preconnect(&self, cdn_url: Option<&str>) -> Result<(), ImagePigError> {
+        let api = self.client.head(self.api_url.clone()).send();
+
+        match cdn_url {
+            Some(cdn_url) => {
+                #[cfg(feature = "download")]
+                let cdn = self.downloader.client.head(cdn_url).send();
+                #[cfg(not(feature = "download"))]
+                let cdn = self.client.head(cdn_url).send();
+                let (api, cdn) = tokio::join!(api, cdn);
+                api.map_err(ImagePigError::from_reqwest)?;
+                cdn.map_err(ImagePigError::from_reqwest)?;
+            }
+            None => {
+                api.await.map_err(ImagePigError::from_reqwest)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn replay_failures(
+        &self,
+    ) -> Result<Vec<Result<APIResponse, ImagePigError>>, ImagePigError> {
+        let Some(path) = &self.journal else {
+            return Ok(Vec::new());
+        };
+
+        let entries = journal::take(path).map_err(|e| ImagePigError::from_io(path, e))?;
+        let mut results = Vec::with_capacity(entries.len());
+
+        for entry in entries {
+            results.push(
+                self.call_api(Request::from_parts(entry.endpoint, entry.payload))
+                    .await,
+            );
         }
+
+        Ok(results)
+    }
+
+    pub async fn execute(&self, mut request: Request) -> Result<APIResponse, ImagePigError> {
+        self.apply_defaults(&mut request);
+
+        if self.trace_requests && request.traceparent_header().is_none() {
+            request = request.traceparent(&trace::generate_traceparent());
+        }
+
+        self.call_api(request).await
     }
 
-    async fn call_api(
+    fn apply_defaults(&self, request: &mut Request) {
+        match request.endpoint() {
+            "flux" => {
+                request
+                    .payload_mut()
+                    .entry("proportion")
+                    .or_insert_with(|| Value::from(self.proportion.to_string()));
+            }
+            "upscale" => {
+                request
+                    .payload_mut()
+                    .entry("upscaling_factor")
+                    .or_insert_with(|| Value::from(self.upscaling_factor as u8));
+            }
+            _ => {}
+        }
+    }
+
+    pub async fn send<R: GenerationRequest>(
         &self,
-        endpoint: &str,
-        payload: serde_json::Map<String, Value>,
+        request: &R,
     ) -> Result<APIResponse, ImagePigError> {
-        let url = format!("{}/{}", self.api_url, endpoint);
-        let response = self
+        let params = request.params()?;
+        self.execute(Request::new(request.endpoint(), params)).await
+    }
+
+    pub async fn gather(
+        &self,
+        requests: impl IntoIterator<Item = Request>,
+    ) -> Vec<Result<APIResponse, ImagePigError>> {
+        let requests: Vec<_> = requests
+            .into_iter()
+            .map(|mut request| {
+                self.apply_defaults(&mut request);
+                request
+            })
+            .collect();
+
+        let (requests, slots) = if self.deduplicate {
+            batch::deduplicate(requests)
+        } else {
+            let slots = (0..requests.len()).collect();
+            (requests, slots)
+        };
+
+        let results = join_in_order(requests.into_iter().map(|request| {
+            let imagepig = self.clone();
+            async move { imagepig.execute(request).await }
+        }))
+        .await;
+
+        batch::fan_out(results, &slots)
+    }
+
+    // an equivalent curl command for reproducing issues, with the API key
+    // replaced by $IMAGEPIG_API_KEY and image data elided
+    pub fn to_curl(&self, request: &Request) -> Result<String, ImagePigError> {
+        let mut request = request.clone();
+        self.apply_defaults(&mut request);
+
+        let url = self.endpoint_url(request.endpoint())?;
+        let payload = logging::redact(&Value::Object(request.payload().clone()));
+
+        let mut headers = vec![
+            "Content-Type: application/json".to_string(),
+            format!("User-Agent: {}", self.user_agent),
+        ];
+        if let Some((header, organization)) = &self.organization {
+            headers.push(format!("{}: {}", header, organization));
+        }
+        if let Some(traceparent) = request.traceparent_header() {
+            headers.push(format!("traceparent: {}", traceparent));
+        }
+
+        // double quotes so the shell expands the key variable
+        let (name, value) = self.auth_scheme.header("$IMAGEPIG_API_KEY");
+        let mut command = format!(
+            "curl -X POST {} \\\n  -H \"{}: {}\"",
+            logging::shell_quote(url.as_str()),
+            name,
+            value
+        );
+        for header in headers {
+            command.push_str(&format!(" \\\n  -H {}", logging::shell_quote(&header)));
+        }
+        command.push_str(&format!(
+            " \\\n  -d {}",
+            logging::shell_quote(&payload.to_string())
+        ));
+        Ok(command)
+    }
+
+    #[cfg(feature = "download")]
+    // like gather(), also recording the sent params, seeds and output hashes
+    pub async fn gather_with_manifest(
+        &self,
+        requests: impl IntoIterator<Item = Request>,
+    ) -> (Vec<Result<APIResponse, ImagePigError>>, Manifest) {
+        let requests: Vec<_> = requests
+            .into_iter()
+            .map(|mut request| {
+                self.apply_defaults(&mut request);
+                request
+            })
+            .collect();
+
+        let results = self.gather(requests.clone()).await;
+        let manifest = Manifest::record(requests, &results).await;
+        (results, manifest)
+    }
+
+    async fn call_api(&self, mut request: Request) -> Result<APIResponse, ImagePigError> {
+        if let Some(hook) = &self.on_request {
+            let (endpoint, payload) = request.endpoint_and_payload_mut();
+            if let Err(error) = hook.call(endpoint, payload) {
+                let result = Err(error);
+                if let Some(hook) = &self.on_response {
+                    hook.call(request.endpoint(), &result);
+                }
+                return result;
+            }
+        }
+
+        let started = Instant::now();
+        let mut result = self.send_request(&request).await;
+
+        for attempt in 1..=self.soft_error_retries {
+            match &result {
+                Err(error) if error.is_soft() && error.is_retryable() => {
+                    tokio::time::sleep(StdDuration::from_secs(attempt.into())).await;
+                    result = self.send_request(&request).await;
+                }
+                _ => break,
+            }
+        }
+
+        self.stats
+            .record(request.endpoint(), started.elapsed(), result.is_err());
+
+        if let Some(hook) = &self.on_response {
+            hook.call(request.endpoint(), &result);
+        }
+
+        if let (Err(error), Some(path)) = (&result, &self.journal) {
+            let (endpoint, payload) = request.into_parts();
+            let entry = journal::JournalEntry {
+                endpoint,
+                payload,
+                error: error.to_string(),
+            };
+            // the request error matters more to the caller than a failed journal write
+            if let Err(e) = journal::append(path, &entry) {
+                log::warn!("cannot write journal {}: {}", path, e);
+            }
+        }
+
+        result
+    }
+
+    // endpoints come from journals, manifests and downstream GenerationRequest impls, so they
+    // must stay relative paths under api_url; otherwise the key could be sent to another host
+    fn endpoint_url(&self, endpoint: &str) -> Result<Url, ImagePigError> {
+        let invalid = || ImagePigError::InvalidEndpoint(endpoint.to_string());
+
+        if endpoint.starts_with('/') || endpoint.contains([':', '?', '#', '\\']) {
+            return Err(invalid());
+        }
+
+        let url = self.api_url.join(endpoint).map_err(|_| invalid())?;
+        if !url.as_str().starts_with(self.api_url.as_str()) {
+            return Err(invalid());
+        }
+        Ok(url)
+    }
+
+    async fn send_request(&self, request: &Request) -> Result<APIResponse, ImagePigError> {
+        let endpoint = request.endpoint();
+        let payload = request.payload();
+        let url = self.endpoint_url(endpoint)?;
+
+        if self.logging {
+            log::debug!(
+                "POST {} {}: <redacted> {}",
+                url,
+                self.auth_scheme.header("").0,
+                logging::redact(&Value::Object(payload.clone()))
+            );
+        }
+
+        let started = Instant::now();
+        let (auth_name, auth_value) = self.auth_scheme.header(&self.api_key);
+        let mut builder = self
             .client
             .post(url)
-            .header("Api-Key", &self.api_key)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(ImagePigError::HttpError)?;
+            .header(auth_name, auth_value)
+            .header("User-Agent", &self.user_agent);
+
+        if let Some((header, organization)) = &self.organization {
+            builder = builder.header(header, organization);
+        }
+
+        if let Some(traceparent) = request.traceparent_header() {
+            builder = builder.header("traceparent", traceparent);
+        }
+
+        if let Some(timeout) = self.timeouts.get(endpoint) {
+            builder = builder.timeout(*timeout);
+        }
+
+        let builder = match &self.progress {
+            Some(progress) => {
+                let body = serde_json::to_vec(payload).map_err(ImagePigError::invalid_input)?;
+                builder
+                    .header(CONTENT_TYPE, "application/json")
+                    .header(CONTENT_LENGTH, body.len())
+                    .body(progress.wrap_body(body))
+            }
+            None => builder.json(payload),
+        };
+
+        let response = builder.send().await.map_err(ImagePigError::from_reqwest)?;
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        self.rate_limit.observe(&headers);
 
-        let content = response
-            .json()
+        let body = response
+            .bytes()
             .await
-            .map_err(|_| ImagePigError::UnexpectedResponse)?;
-        Ok(APIResponse { content })
+            .map_err(ImagePigError::from_reqwest)?;
+
+        if self.logging {
+            let summary = serde_json::from_slice(&body)
+                .map(|content| logging::redact(&content).to_string())
+                .unwrap_or_else(|_| format!("<{} bytes>", body.len()));
+            log::debug!(
+                "{} {} in {:?}: {}",
+                status,
+                endpoint,
+                started.elapsed(),
+                summary
+            );
+        }
+
+        if !status.is_success() {
+            let content = serde_json::from_slice(&body)
+                .unwrap_or_else(|_| Value::from(String::from_utf8_lossy(&body).into_owned()));
+            return Err(ImagePigError::from_api(status.as_u16(), &content));
+        }
+
+        let content = parse_json(body)?;
+
+        // the API occasionally answers 200 with an error object instead of an image
+        let has_image = ["image_data", "image_url"]
+            .iter()
+            .any(|field| content.get(field).is_some_and(|v| !v.is_null()));
+        if !has_image && content.get("error").is_some_and(|v| !v.is_null()) {
+            return Err(ImagePigError::from_api(status.as_u16(), &content));
+        }
+
+        Ok(APIResponse {
+            content,
+            endpoint: endpoint.to_string(),
+            status,
+            headers,
+            #[cfg(feature = "download")]
+            downloader: self.downloader.clone(),
+        })
     }
 
     pub async fn default(
         &self,
         prompt: &str,
         negative_prompt: Option<&str>,
-        extra_params: Option<serde_json::Map<String, Value>>,
+        extra_params: Option<Params>,
     ) -> Result<APIResponse, ImagePigError> {
-        let mut params = extra_params.unwrap_or_default();
-        params.insert(
-            "positive_prompt".to_string(),
-            serde_json::Value::from(prompt),
-        );
-        params.insert(
-            "negative_prompt".to_string(),
-            serde_json::Value::from(negative_prompt.unwrap_or_default()),
-        );
-        self.call_api("", params).await
+        self.execute(Request::default(prompt, negative_prompt, extra_params))
+            .await
     }
 
     pub async fn xl(
         &self,
         prompt: &str,
         negative_prompt: Option<&str>,
-        extra_params: Option<serde_json::Map<String, Value>>,
+        extra_params: Option<Params>,
     ) -> Result<APIResponse, ImagePigError> {
-        let mut params = extra_params.unwrap_or_default();
-        params.insert(
-            "positive_prompt".to_string(),
-            serde_json::Value::from(prompt),
-        );
-        params.insert(
-            "negative_prompt".to_string(),
-            serde_json::Value::from(negative_prompt.unwrap_or_default()),
-        );
-        self.call_api("xl", params).await
+        self.execute(Request::xl(prompt, negative_prompt, extra_params))
+            .await
     }
 
     pub async fn flux(
         &self,
         prompt: &str,
         proportion: Option<Proportion>,
-        extra_params: Option<serde_json::Map<String, Value>>,
+        extra_params: Option<Params>,
     ) -> Result<APIResponse, ImagePigError> {
-        let mut params = extra_params.unwrap_or_default();
-        params.insert(
-            "positive_prompt".to_string(),
-            serde_json::Value::from(prompt),
-        );
-        params.insert(
-            "proportion".to_string(),
-            serde_json::Value::from(proportion.unwrap_or(Proportion::Landscape).to_string()),
-        );
-        self.call_api("flux", params).await
+        self.execute(Request::flux(prompt, proportion, extra_params))
+            .await
     }
 
-    pub async fn faceswap<T: Image>(
+    pub async fn faceswap<S: Image, T: Image>(
         &self,
-        source_image: T,
+        source_image: S,
         target_image: T,
-        extra_params: Option<serde_json::Map<String, Value>>,
+        extra_params: Option<Params>,
     ) -> Result<APIResponse, ImagePigError> {
-        let mut params = extra_params.unwrap_or_default();
-        source_image
-            .prepare_image("source_image", &mut params)
-            .unwrap();
-        target_image
-            .prepare_image("target_image", &mut params)
-            .unwrap();
-        self.call_api("faceswap", params).await
+        self.execute(Request::faceswap(source_image, target_image, extra_params)?)
+            .await
+    }
+
+    // the source is encoded once and swapped onto all targets concurrently
+    pub async fn faceswap_many<S: Image, T: Image>(
+        &self,
+        source_image: S,
+        target_images: impl IntoIterator<Item = T>,
+        extra_params: Option<Params>,
+    ) -> Result<Vec<Result<APIResponse, ImagePigError>>, ImagePigError> {
+        let source = PreparedImage::new(source_image)?;
+        let requests: Vec<_> = target_images
+            .into_iter()
+            .map(|target| Request::faceswap(source.clone(), target, extra_params.clone()))
+            .collect();
+
+        Ok(join_in_order(requests.into_iter().map(|request| {
+            let imagepig = self.clone();
+            async move { imagepig.execute(request?).await }
+        }))
+        .await)
     }
 
     pub async fn upscale<T: Image>(
         &self,
         image: T,
         factor: Option<UpscalingFactor>,
-        extra_params: Option<serde_json::Map<String, Value>>,
+        extra_params: Option<Params>,
     ) -> Result<APIResponse, ImagePigError> {
-        let mut params = extra_params.unwrap_or_default();
-        image.prepare_image("image", &mut params).unwrap();
-        params.insert(
-            "upscaling_factor".to_string(),
-            serde_json::Value::from(factor.unwrap_or(UpscalingFactor::Two) as u8),
-        );
-        self.call_api("upscale", params).await
+        self.execute(Request::upscale(image, factor, extra_params)?)
+            .await
     }
 
     pub async fn cutout<T: Image>(
         &self,
         image: T,
-        extra_params: Option<serde_json::Map<String, Value>>,
+        extra_params: Option<Params>,
     ) -> Result<APIResponse, ImagePigError> {
-        let mut params = extra_params.unwrap_or_default();
-        image.prepare_image("image", &mut params).unwrap();
-        self.call_api("cutout", params).await
+        self.execute(Request::cutout(image, extra_params)?).await
     }
 
     pub async fn replace<T: Image>(
@@ -314,25 +1266,23 @@ impl ImagePig {
         select_prompt: &str,
         positive_prompt: &str,
         negative_prompt: Option<&str>,
-        extra_params: Option<serde_json::Map<String, Value>>,
+        extra_params: Option<Params>,
     ) -> Result<APIResponse, ImagePigError> {
-        let mut params = extra_params.unwrap_or_default();
-        image.prepare_image("image", &mut params).unwrap();
-        params.insert(
-            "select_prompt".to_string(),
-            serde_json::Value::from(select_prompt),
-        );
-        params.insert(
-            "positive_prompt".to_string(),
-            serde_json::Value::from(positive_prompt),
-        );
-        params.insert(
-            "negative_prompt".to_string(),
-            serde_json::Value::from(negative_prompt.unwrap_or_default()),
-        );
-        self.call_api("replace", params).await
+        self.execute(Request::replace(
+            image,
+            select_prompt,
+            positive_prompt,
+            negative_prompt,
+            extra_params,
+        )?)
+        .await
     }
 
+    #[deprecated(
+        since = "2.0.0",
+        note = "use `outpaint_with` and `OutpaintOptions` instead"
+    )]
+    #[allow(clippy::too_many_arguments)]
     pub async fn outpaint<T: Image>(
         &self,
         image: T,
@@ -342,34 +1292,103 @@ impl ImagePig {
         bottom: Option<u32>,
         left: Option<u32>,
         negative_prompt: Option<&str>,
-        extra_params: Option<serde_json::Map<String, Value>>,
+        extra_params: Option<Params>,
     ) -> Result<APIResponse, ImagePigError> {
-        let mut params = extra_params.unwrap_or_default();
-        image.prepare_image("image", &mut params).unwrap();
-        params.insert(
-            "positive_prompt".to_string(),
-            serde_json::Value::from(positive_prompt),
-        );
-        params.insert(
-            "negative_prompt".to_string(),
-            serde_json::Value::from(negative_prompt.unwrap_or_default()),
-        );
-        params.insert(
-            "top".to_string(),
-            serde_json::Value::from(top.unwrap_or_default()),
-        );
-        params.insert(
-            "right".to_string(),
-            serde_json::Value::from(right.unwrap_or_default()),
-        );
-        params.insert(
-            "bottom".to_string(),
-            serde_json::Value::from(bottom.unwrap_or_default()),
-        );
-        params.insert(
-            "left".to_string(),
-            serde_json::Value::from(left.unwrap_or_default()),
-        );
-        self.call_api("outpaint", params).await
+        let mut options = OutpaintOptions::new()
+            .top(top.unwrap_or_default())
+            .right(right.unwrap_or_default())
+            .bottom(bottom.unwrap_or_default())
+            .left(left.unwrap_or_default());
+
+        if let Some(negative_prompt) = negative_prompt {
+            options = options.negative_prompt(negative_prompt);
+        }
+
+        self.outpaint_with(image, positive_prompt, options, extra_params)
+            .await
+    }
+
+    pub async fn outpaint_with<T: Image>(
+        &self,
+        image: T,
+        positive_prompt: &str,
+        options: OutpaintOptions,
+        extra_params: Option<Params>,
+    ) -> Result<APIResponse, ImagePigError> {
+        self.execute(Request::outpaint(
+            image,
+            positive_prompt,
+            options,
+            extra_params,
+        )?)
+        .await
+    }
+}
+
+#[cfg(feature = "download")]
+fn indexed_path(path: &str, index: usize) -> String {
+    if path.contains("{index}") {
+        return path.replace("{index}", &index.to_string());
     }
+
+    match path.rsplit_once('.') {
+        Some((stem, extension)) if !extension.contains('/') => {
+            format!("{}-{}.{}", stem, index, extension)
+        }
+        _ => format!("{}-{}", path, index),
+    }
+}
+
+// generations take much longer than the editing endpoints
+fn default_timeouts() -> HashMap<String, StdDuration> {
+    [
+        ("", 300),
+        ("xl", 300),
+        ("flux", 300),
+        ("replace", 180),
+        ("outpaint", 180),
+        ("faceswap", 120),
+        ("upscale", 120),
+        ("cutout", 120),
+    ]
+    .into_iter()
+    .map(|(endpoint, secs)| (endpoint.to_string(), StdDuration::from_secs(secs)))
+    .collect()
+}
+
+// runs the futures concurrently and returns their outputs in submission order,
+// dropping the returned future aborts whatever is still running
+pub(crate) async fn join_in_order<F>(futures: impl IntoIterator<Item = F>) -> Vec<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let mut tasks = JoinSet::new();
+
+    for (index, future) in futures.into_iter().enumerate() {
+        tasks.spawn(async move { (index, future.await) });
+    }
+
+    let mut results: Vec<_> = (0..tasks.len()).map(|_| None).collect();
+
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, result)) => results[index] = Some(result),
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
+    }
+
+    results.into_iter().flatten().collect()
+}
+
+#[cfg(not(feature = "simd-json"))]
+fn parse_json(body: Bytes) -> Result<Value, ImagePigError> {
+    serde_json::from_slice(&body).map_err(ImagePigError::decode)
+}
+
+// simd-json parses in place, which needs an owned, mutable buffer
+#[cfg(feature = "simd-json")]
+fn parse_json(body: Bytes) -> Result<Value, ImagePigError> {
+    let mut body = Vec::from(body);
+    simd_json::serde::from_slice(&mut body).map_err(ImagePigError::decode)
 }
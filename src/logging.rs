@@ -0,0 +1,26 @@
+use serde_json::Value;
+
+pub(crate) fn redact(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(
+                    |(key, value)| match (key.ends_with("_data"), value.as_str()) {
+                        (true, Some(data)) => (
+                            key.clone(),
+                            Value::from(format!("<{} bytes of base64>", data.len())),
+                        ),
+                        _ => (key.clone(), redact(value)),
+                    },
+                )
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact).collect()),
+        _ => value.clone(),
+    }
+}
+
+// single-quoted for POSIX shells
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
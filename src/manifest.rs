@@ -0,0 +1,105 @@
+use crate::{APIResponse, ImagePigError, Request};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub endpoint: String,
+    pub params: serde_json::Map<String, Value>,
+    pub seed: Option<u64>,
+    // one hash per image, in the order of APIResponse::images()
+    #[serde(default)]
+    pub sha256: Vec<String>,
+    pub error: Option<String>,
+}
+
+// everything needed to re-execute or audit a batch run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: String,
+    // seconds since the Unix epoch
+    pub created_at: u64,
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    // downloads every image of a successful output to hash it
+    pub(crate) async fn record(
+        requests: Vec<Request>,
+        results: &[Result<APIResponse, ImagePigError>],
+    ) -> Self {
+        let mut entries = Vec::with_capacity(requests.len());
+
+        for (request, result) in requests.into_iter().zip(results) {
+            let (endpoint, mut params) = request.into_parts();
+            let entry = match result {
+                Ok(response) => {
+                    let seed = response.seed();
+                    // pin the seed the API picked so a re-run produces the same image
+                    if let Some(seed) = seed {
+                        params.entry("seed").or_insert(Value::from(seed));
+                    }
+                    let (sha256, error) = match hashes(response).await {
+                        Ok(hashes) => (hashes, None),
+                        Err(e) => (Vec::new(), Some(e.to_string())),
+                    };
+                    ManifestEntry {
+                        endpoint,
+                        params,
+                        seed,
+                        sha256,
+                        error,
+                    }
+                }
+                Err(e) => ManifestEntry {
+                    endpoint,
+                    params,
+                    seed: None,
+                    sha256: Vec::new(),
+                    error: Some(e.to_string()),
+                },
+            };
+            entries.push(entry);
+        }
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            entries,
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, ImagePigError> {
+        let json = std::fs::read_to_string(path).map_err(|e| ImagePigError::from_io(path, e))?;
+        serde_json::from_str(&json).map_err(ImagePigError::decode)
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), ImagePigError> {
+        let json = serde_json::to_vec_pretty(self).map_err(ImagePigError::invalid_input)?;
+        std::fs::write(path, json).map_err(|e| ImagePigError::from_io(path, e))
+    }
+
+    // the recorded requests, ready to be passed to ImagePig::gather()
+    pub fn requests(&self) -> Vec<Request> {
+        self.entries
+            .iter()
+            .map(|entry| Request::from_parts(entry.endpoint.clone(), entry.params.clone()))
+            .collect()
+    }
+}
+
+async fn hashes(response: &APIResponse) -> Result<Vec<String>, ImagePigError> {
+    let mut hashes = Vec::new();
+    for image in response.images() {
+        hashes.push(hex(&Sha256::digest(image.data().await?)));
+    }
+    Ok(hashes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,123 @@
+use crate::ImagePigError;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy)]
+pub enum OutputFormat {
+    Jpeg,
+    Png,
+    Webp,
+    Avif,
+}
+
+#[cfg(feature = "image")]
+impl From<OutputFormat> for image::ImageFormat {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Jpeg => image::ImageFormat::Jpeg,
+            OutputFormat::Png => image::ImageFormat::Png,
+            OutputFormat::Webp => image::ImageFormat::WebP,
+            OutputFormat::Avif => image::ImageFormat::Avif,
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Params {
+    values: serde_json::Map<String, Value>,
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn seed(self, seed: u64) -> Self {
+        self.raw("seed", seed)
+    }
+
+    pub fn format(self, format: OutputFormat) -> Self {
+        self.raw("format", format.to_string())
+    }
+
+    pub fn storage_days(self, days: u32) -> Self {
+        self.raw("storage_days", days)
+    }
+
+    // escape hatch for options the typed setters do not cover yet
+    pub fn raw(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.values.insert(key.to_string(), value.into());
+        self
+    }
+
+    // merges the fields of any value that serializes to a JSON object, e.g. a caller's own options struct
+    pub fn extend<T: Serialize>(mut self, value: &T) -> Result<Self, ImagePigError> {
+        match serde_json::to_value(value).map_err(ImagePigError::invalid_params)? {
+            Value::Object(map) => {
+                self.values.extend(map);
+                Ok(self)
+            }
+            _ => Err(ImagePigError::invalid_params(
+                "extra parameters must serialize to a JSON object",
+            )),
+        }
+    }
+
+    pub fn from_serialize<T: Serialize>(value: &T) -> Result<Self, ImagePigError> {
+        Self::new().extend(value)
+    }
+
+    pub(crate) fn from_map(values: serde_json::Map<String, Value>) -> Self {
+        Self { values }
+    }
+
+    pub(crate) fn into_map(self) -> serde_json::Map<String, Value> {
+        self.values
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OutpaintOptions {
+    pub(crate) top: u32,
+    pub(crate) right: u32,
+    pub(crate) bottom: u32,
+    pub(crate) left: u32,
+    pub(crate) negative_prompt: Option<String>,
+}
+
+impl OutpaintOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn top(mut self, pixels: u32) -> Self {
+        self.top = pixels;
+        self
+    }
+
+    pub fn right(mut self, pixels: u32) -> Self {
+        self.right = pixels;
+        self
+    }
+
+    pub fn bottom(mut self, pixels: u32) -> Self {
+        self.bottom = pixels;
+        self
+    }
+
+    pub fn left(mut self, pixels: u32) -> Self {
+        self.left = pixels;
+        self
+    }
+
+    pub fn negative_prompt(mut self, negative_prompt: &str) -> Self {
+        self.negative_prompt = Some(negative_prompt.to_string());
+        self
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{
+    APIResponse, ImagePig, ImagePigError, OutpaintOptions, Params, PreparedImage, Request,
+    UpscalingFactor,
+};
+
+#[derive(Debug, Clone)]
+enum Step {
+    Upscale(Option<UpscalingFactor>, Option<Params>),
+    Cutout(Option<Params>),
+    Faceswap(PreparedImage, Option<Params>),
+    Replace {
+        select_prompt: String,
+        positive_prompt: String,
+        negative_prompt: Option<String>,
+        params: Option<Params>,
+    },
+    Outpaint {
+        positive_prompt: String,
+        options: OutpaintOptions,
+        params: Option<Params>,
+    },
+}
+
+#[derive(Debug)]
+pub struct PipelineOutput {
+    pub output: APIResponse,
+    pub intermediate: Vec<APIResponse>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    first: Request,
+    steps: Vec<Step>,
+}
+
+impl Pipeline {
+    pub fn new(first: Request) -> Self {
+        Self {
+            first,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn upscale(
+        mut self,
+        factor: Option<UpscalingFactor>,
+        extra_params: Option<Params>,
+    ) -> Self {
+        self.steps.push(Step::Upscale(factor, extra_params));
+        self
+    }
+
+    pub fn cutout(mut self, extra_params: Option<Params>) -> Self {
+        self.steps.push(Step::Cutout(extra_params));
+        self
+    }
+
+    // swaps the face from source_image onto the previous step's output
+    pub fn faceswap(mut self, source_image: PreparedImage, extra_params: Option<Params>) -> Self {
+        self.steps.push(Step::Faceswap(source_image, extra_params));
+        self
+    }
+
+    pub fn replace(
+        mut self,
+        select_prompt: &str,
+        positive_prompt: &str,
+        negative_prompt: Option<&str>,
+        extra_params: Option<Params>,
+    ) -> Self {
+        self.steps.push(Step::Replace {
+            select_prompt: select_prompt.to_string(),
+            positive_prompt: positive_prompt.to_string(),
+            negative_prompt: negative_prompt.map(String::from),
+            params: extra_params,
+        });
+        self
+    }
+
+    pub fn outpaint(
+        mut self,
+        positive_prompt: &str,
+        options: OutpaintOptions,
+        extra_params: Option<Params>,
+    ) -> Self {
+        self.steps.push(Step::Outpaint {
+            positive_prompt: positive_prompt.to_string(),
+            options,
+            params: extra_params,
+        });
+        self
+    }
+
+    pub async fn run(self, imagepig: &ImagePig) -> Result<PipelineOutput, ImagePigError> {
+        let mut output = imagepig.execute(self.first).await?;
+        let mut intermediate = Vec::with_capacity(self.steps.len());
+
+        for step in self.steps {
+            let image = next_input(&output).await?;
+            let request = match step {
+                Step::Upscale(factor, params) => Request::upscale(image, factor, params)?,
+                Step::Cutout(params) => Request::cutout(image, params)?,
+                Step::Faceswap(source, params) => Request::faceswap(source, image, params)?,
+                Step::Replace {
+                    select_prompt,
+                    positive_prompt,
+                    negative_prompt,
+                    params,
+                } => Request::replace(
+                    image,
+                    &select_prompt,
+                    &positive_prompt,
+                    negative_prompt.as_deref(),
+                    params,
+                )?,
+                Step::Outpaint {
+                    positive_prompt,
+                    options,
+                    params,
+                } => Request::outpaint(image, &positive_prompt, options, params)?,
+            };
+
+            let next = imagepig.execute(request).await?;
+            intermediate.push(std::mem::replace(&mut output, next));
+        }
+
+        Ok(PipelineOutput {
+            output,
+            intermediate,
+        })
+    }
+}
+
+// a hosted result is passed on by URL, an inline one by its bytes
+async fn next_input(response: &APIResponse) -> Result<PreparedImage, ImagePigError> {
+    let response = response.single()?;
+    match response.url() {
+        Some(url) => PreparedImage::new(url.as_str()),
+        None => PreparedImage::new(response.data().await?),
+    }
+}
@@ -0,0 +1,61 @@
+use bytes::Bytes;
+use futures_util::StreamExt;
+use reqwest::Body;
+use std::fmt;
+use std::sync::Arc;
+
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transfer {
+    Upload,
+    Download,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    pub transfer: Transfer,
+    pub bytes: u64,
+    pub total: Option<u64>,
+}
+
+#[derive(Clone)]
+pub(crate) struct ProgressCallback(Arc<dyn Fn(Progress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub fn report(&self, transfer: Transfer, bytes: u64, total: Option<u64>) {
+        (self.0)(Progress {
+            transfer,
+            bytes,
+            total,
+        })
+    }
+
+    // progress is reported as the HTTP client pulls each chunk of the body
+    pub fn wrap_body(&self, body: Vec<u8>) -> Body {
+        let body = Bytes::from(body);
+        let total = body.len() as u64;
+        let chunks: Vec<Bytes> = (0..body.len())
+            .step_by(UPLOAD_CHUNK_SIZE)
+            .map(|start| body.slice(start..(start + UPLOAD_CHUNK_SIZE).min(body.len())))
+            .collect();
+
+        let callback = self.clone();
+        let mut sent = 0;
+        Body::wrap_stream(futures_util::stream::iter(chunks).map(move |chunk| {
+            sent += chunk.len() as u64;
+            callback.report(Transfer::Upload, sent, Some(total));
+            Ok::<_, std::io::Error>(chunk)
+        }))
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
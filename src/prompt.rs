@@ -0,0 +1,94 @@
+use crate::ImagePigError;
+use std::fmt;
+
+#[derive(Debug, Clone, Default)]
+pub struct Prompt {
+    terms: Vec<(String, f32)>,
+}
+
+impl Prompt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(mut self, text: &str) -> Self {
+        self.terms.push((text.trim().to_string(), 1.0));
+        self
+    }
+
+    // NaN, infinite and negative weights have no meaning in the (term:weight) syntax
+    pub fn term(mut self, text: &str, weight: f32) -> Result<Self, ImagePigError> {
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(ImagePigError::InvalidWeight(weight));
+        }
+        self.terms.push((text.trim().to_string(), weight));
+        Ok(self)
+    }
+
+    // length in characters of the serialized prompt
+    pub fn len(&self) -> usize {
+        self.to_string().chars().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    // the API documents no prompt length limit, so there is no built-in default;
+    // callers pass the limit they enforce (a gateway's, or a character budget for the model)
+    pub fn validate(&self, limit: usize) -> Result<(), ImagePigError> {
+        let length = self.len();
+        if length > limit {
+            return Err(ImagePigError::PromptTooLong { length, limit });
+        }
+        Ok(())
+    }
+
+    // drops words from the end of unweighted text first, and only then whole weighted terms;
+    // like validate(), the limit comes from the caller as the API documents none
+    pub fn truncate(mut self, limit: usize) -> Self {
+        while self.len() > limit {
+            match self.terms.iter().rposition(|(_, weight)| *weight == 1.0) {
+                Some(index) => {
+                    let text = &mut self.terms[index].0;
+                    match text.rfind(char::is_whitespace) {
+                        Some(end) => text.truncate(text[..end].trim_end().len()),
+                        None => {
+                            self.terms.remove(index);
+                        }
+                    }
+                }
+                None => {
+                    self.terms.pop();
+                }
+            }
+        }
+        self
+    }
+}
+
+// serialized using the (term:weight) syntax of common Stable Diffusion front ends; the
+// Image Pig API does not document prompt weighting, so whether its models honor the
+// weights (rather than reading the parentheses as text) is unverified
+impl fmt::Display for Prompt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (text, weight)) in self.terms.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            if *weight == 1.0 {
+                write!(f, "{}", text)?;
+            } else {
+                write!(f, "({}:{})", text, weight)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<Prompt> for String {
+    fn from(prompt: Prompt) -> Self {
+        prompt.to_string()
+    }
+}
@@ -0,0 +1,64 @@
+use reqwest::header::HeaderMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// values larger than this are treated as Unix timestamps rather than seconds from now
+const TIMESTAMP_THRESHOLD: u64 = 1_000_000_000;
+
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub reset_at: Option<SystemTime>,
+}
+
+impl RateLimit {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let number = |names: &[&str]| {
+            names.iter().find_map(|name| {
+                headers
+                    .get(*name)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+            })
+        };
+
+        let limit = number(&["x-ratelimit-limit", "ratelimit-limit"]);
+        let remaining = number(&["x-ratelimit-remaining", "ratelimit-remaining"]);
+        let reset_at =
+            number(&["x-ratelimit-reset", "ratelimit-reset", "retry-after"]).map(|reset| {
+                if reset > TIMESTAMP_THRESHOLD {
+                    UNIX_EPOCH + Duration::from_secs(reset)
+                } else {
+                    SystemTime::now() + Duration::from_secs(reset)
+                }
+            });
+
+        if limit.is_none() && remaining.is_none() && reset_at.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            limit,
+            remaining,
+            reset_at,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RateLimitTracker {
+    latest: Arc<Mutex<Option<RateLimit>>>,
+}
+
+impl RateLimitTracker {
+    pub fn observe(&self, headers: &HeaderMap) {
+        if let Some(rate_limit) = RateLimit::from_headers(headers) {
+            *self.latest.lock().unwrap() = Some(rate_limit);
+        }
+    }
+
+    pub fn latest(&self) -> Option<RateLimit> {
+        self.latest.lock().unwrap().clone()
+    }
+}
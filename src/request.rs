@@ -0,0 +1,178 @@
+use crate::{Image, ImagePigError, OutpaintOptions, Params, Proportion, UpscalingFactor};
+use serde_json::Value;
+
+// implemented by downstream crates to send their own endpoints through ImagePig::send()
+pub trait GenerationRequest {
+    fn endpoint(&self) -> &str;
+
+    fn params(&self) -> Result<Params, ImagePigError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    endpoint: String,
+    payload: serde_json::Map<String, Value>,
+    traceparent: Option<String>,
+}
+
+impl Request {
+    pub fn new(endpoint: &str, params: Params) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            payload: params.into_map(),
+            traceparent: None,
+        }
+    }
+
+    pub(crate) fn from_parts(endpoint: String, payload: serde_json::Map<String, Value>) -> Self {
+        Self {
+            endpoint,
+            payload,
+            traceparent: None,
+        }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn payload(&self) -> &serde_json::Map<String, Value> {
+        &self.payload
+    }
+
+    pub(crate) fn payload_mut(&mut self) -> &mut serde_json::Map<String, Value> {
+        &mut self.payload
+    }
+
+    pub(crate) fn endpoint_and_payload_mut(
+        &mut self,
+    ) -> (&str, &mut serde_json::Map<String, Value>) {
+        (&self.endpoint, &mut self.payload)
+    }
+
+    pub(crate) fn into_parts(self) -> (String, serde_json::Map<String, Value>) {
+        (self.endpoint, self.payload)
+    }
+
+    // W3C trace context header sent with the request, e.g. "00-<trace-id>-<parent-id>-01"
+    pub fn traceparent(mut self, traceparent: &str) -> Self {
+        self.traceparent = Some(traceparent.to_string());
+        self
+    }
+
+    pub fn traceparent_header(&self) -> Option<&str> {
+        self.traceparent.as_deref()
+    }
+
+    pub(crate) fn insert(&mut self, key: &str, value: impl Into<Value>) {
+        self.payload.insert(key.to_string(), value.into());
+    }
+
+    pub fn default(
+        prompt: &str,
+        negative_prompt: Option<&str>,
+        extra_params: Option<Params>,
+    ) -> Self {
+        let mut request = Self::new("", extra_params.unwrap_or_default());
+        request.insert("positive_prompt", prompt);
+        request.insert("negative_prompt", negative_prompt.unwrap_or_default());
+        request
+    }
+
+    pub fn xl(prompt: &str, negative_prompt: Option<&str>, extra_params: Option<Params>) -> Self {
+        let mut request = Self::new("xl", extra_params.unwrap_or_default());
+        request.insert("positive_prompt", prompt);
+        request.insert("negative_prompt", negative_prompt.unwrap_or_default());
+        request
+    }
+
+    // without a proportion the client's default is used when the request is sent
+    pub fn flux(
+        prompt: &str,
+        proportion: Option<Proportion>,
+        extra_params: Option<Params>,
+    ) -> Self {
+        let mut request = Self::new("flux", extra_params.unwrap_or_default());
+        request.insert("positive_prompt", prompt);
+        if let Some(proportion) = proportion {
+            request.insert("proportion", proportion.to_string());
+        }
+        request
+    }
+
+    pub fn faceswap<S: Image, T: Image>(
+        source_image: S,
+        target_image: T,
+        extra_params: Option<Params>,
+    ) -> Result<Self, ImagePigError> {
+        let mut request = Self::new("faceswap", extra_params.unwrap_or_default());
+        source_image.prepare_image("source_image", &mut request.payload)?;
+        target_image.prepare_image("target_image", &mut request.payload)?;
+        Ok(request)
+    }
+
+    // without a factor the client's default is used when the request is sent
+    pub fn upscale<T: Image>(
+        image: T,
+        factor: Option<UpscalingFactor>,
+        extra_params: Option<Params>,
+    ) -> Result<Self, ImagePigError> {
+        let mut request = Self::new("upscale", extra_params.unwrap_or_default());
+        image.prepare_image("image", &mut request.payload)?;
+        if let Some(factor) = factor {
+            request.insert("upscaling_factor", factor as u8);
+        }
+        Ok(request)
+    }
+
+    pub fn cutout<T: Image>(image: T, extra_params: Option<Params>) -> Result<Self, ImagePigError> {
+        let mut request = Self::new("cutout", extra_params.unwrap_or_default());
+        image.prepare_image("image", &mut request.payload)?;
+        Ok(request)
+    }
+
+    pub fn replace<T: Image>(
+        image: T,
+        select_prompt: &str,
+        positive_prompt: &str,
+        negative_prompt: Option<&str>,
+        extra_params: Option<Params>,
+    ) -> Result<Self, ImagePigError> {
+        let mut request = Self::new("replace", extra_params.unwrap_or_default());
+        image.prepare_image("image", &mut request.payload)?;
+        request.insert("select_prompt", select_prompt);
+        request.insert("positive_prompt", positive_prompt);
+        request.insert("negative_prompt", negative_prompt.unwrap_or_default());
+        Ok(request)
+    }
+
+    pub fn outpaint<T: Image>(
+        image: T,
+        positive_prompt: &str,
+        options: OutpaintOptions,
+        extra_params: Option<Params>,
+    ) -> Result<Self, ImagePigError> {
+        let mut request = Self::new("outpaint", extra_params.unwrap_or_default());
+        image.prepare_image("image", &mut request.payload)?;
+        request.insert("positive_prompt", positive_prompt);
+        request.insert(
+            "negative_prompt",
+            options.negative_prompt.unwrap_or_default(),
+        );
+        request.insert("top", options.top);
+        request.insert("right", options.right);
+        request.insert("bottom", options.bottom);
+        request.insert("left", options.left);
+        Ok(request)
+    }
+}
+
+impl GenerationRequest for Request {
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    fn params(&self) -> Result<Params, ImagePigError> {
+        Ok(Params::from_map(self.payload.clone()))
+    }
+}
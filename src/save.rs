@@ -0,0 +1,14 @@
+use crate::{APIResponse, ImagePigError};
+use std::future::Future;
+
+pub trait SaveAs: Future<Output = Result<APIResponse, ImagePigError>> + Sized {
+    fn save_as(self, path: &str) -> impl Future<Output = Result<APIResponse, ImagePigError>> {
+        async move {
+            let response = self.await?;
+            response.save(path).await?;
+            Ok(response)
+        }
+    }
+}
+
+impl<F> SaveAs for F where F: Future<Output = Result<APIResponse, ImagePigError>> {}
@@ -0,0 +1,163 @@
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage};
+use serde_json::Value;
+
+use crate::{APIResponse, ImagePigError};
+
+const HASH_SIZE: usize = 8;
+const DCT_SIZE: usize = 32;
+const SSIM_SIZE: u32 = 256;
+const SSIM_WINDOW: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PerceptualHash(pub u64);
+
+impl PerceptualHash {
+    // DCT-based pHash: bits of the lowest frequencies compared against their median
+    pub fn new(image: &DynamicImage) -> Self {
+        let gray = image
+            .resize_exact(DCT_SIZE as u32, DCT_SIZE as u32, FilterType::Triangle)
+            .to_luma8();
+        let pixels: Vec<f64> = gray.pixels().map(|p| p.0[0] as f64).collect();
+
+        let mut coefficients = Vec::with_capacity(HASH_SIZE * HASH_SIZE);
+        for u in 0..HASH_SIZE {
+            for v in 0..HASH_SIZE {
+                coefficients.push(dct(&pixels, u, v));
+            }
+        }
+
+        // the DC term only reflects overall brightness
+        let mut sorted = coefficients[1..].to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let median = sorted[sorted.len() / 2];
+
+        let bits = coefficients
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c > median)
+            .fold(0u64, |bits, (i, _)| bits | (1 << i));
+        Self(bits)
+    }
+
+    pub fn distance(&self, other: &PerceptualHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+}
+
+fn dct(pixels: &[f64], u: usize, v: usize) -> f64 {
+    let n = DCT_SIZE as f64;
+    let mut sum = 0.0;
+    for x in 0..DCT_SIZE {
+        for y in 0..DCT_SIZE {
+            sum += pixels[y * DCT_SIZE + x]
+                * (((2 * x + 1) as f64 * u as f64 * std::f64::consts::PI) / (2.0 * n)).cos()
+                * (((2 * y + 1) as f64 * v as f64 * std::f64::consts::PI) / (2.0 * n)).cos();
+        }
+    }
+    sum
+}
+
+// mean SSIM over 8x8 windows of both images scaled to the same grayscale size, 1.0 means identical
+pub fn ssim(a: &DynamicImage, b: &DynamicImage) -> f64 {
+    let a = a
+        .resize_exact(SSIM_SIZE, SSIM_SIZE, FilterType::Triangle)
+        .to_luma8();
+    let b = b
+        .resize_exact(SSIM_SIZE, SSIM_SIZE, FilterType::Triangle)
+        .to_luma8();
+
+    let mut total = 0.0;
+    let mut windows = 0;
+    for y in (0..SSIM_SIZE).step_by(SSIM_WINDOW as usize) {
+        for x in (0..SSIM_SIZE).step_by(SSIM_WINDOW as usize) {
+            total += window_ssim(&a, &b, x, y);
+            windows += 1;
+        }
+    }
+    total / windows as f64
+}
+
+fn window_ssim(a: &GrayImage, b: &GrayImage, x0: u32, y0: u32) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let values = |image: &GrayImage| -> Vec<f64> {
+        (y0..y0 + SSIM_WINDOW)
+            .flat_map(|y| (x0..x0 + SSIM_WINDOW).map(move |x| (x, y)))
+            .map(|(x, y)| image.get_pixel(x, y).0[0] as f64)
+            .collect()
+    };
+    let (a, b) = (values(a), values(b));
+    let n = a.len() as f64;
+
+    let mean_a = a.iter().sum::<f64>() / n;
+    let mean_b = b.iter().sum::<f64>() / n;
+    let (mut var_a, mut var_b, mut covariance) = (0.0, 0.0, 0.0);
+    for (pa, pb) in a.iter().zip(&b) {
+        var_a += (pa - mean_a).powi(2);
+        var_b += (pb - mean_b).powi(2);
+        covariance += (pa - mean_a) * (pb - mean_b);
+    }
+    let (var_a, var_b, covariance) = (var_a / n, var_b / n, covariance / n);
+
+    ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+        / ((mean_a.powi(2) + mean_b.powi(2) + C1) * (var_a + var_b + C2))
+}
+
+// for every hash, the index of an earlier hash within max_distance of it
+pub fn find_duplicates(hashes: &[PerceptualHash], max_distance: u32) -> Vec<Option<usize>> {
+    hashes
+        .iter()
+        .enumerate()
+        .map(|(i, hash)| {
+            hashes[..i]
+                .iter()
+                .position(|earlier| earlier.distance(hash) <= max_distance)
+        })
+        .collect()
+}
+
+// for every result of gather(), the index of an earlier result with a near-identical
+// image; failed requests and images that cannot be decoded are never flagged or matched.
+// Hosted images are downloaded to hash them and kept inline, so saving them later does
+// not download them again
+pub async fn dedup(
+    results: &mut [Result<APIResponse, ImagePigError>],
+    max_distance: u32,
+) -> Vec<Option<usize>> {
+    let mut hashes = Vec::with_capacity(results.len());
+    for result in results.iter_mut() {
+        hashes.push(match result {
+            Ok(response) => hash(response).await,
+            Err(_) => None,
+        });
+    }
+
+    hashes
+        .iter()
+        .enumerate()
+        .map(|(i, hash)| {
+            let hash = hash.as_ref()?;
+            hashes[..i].iter().position(|earlier| {
+                earlier
+                    .as_ref()
+                    .is_some_and(|earlier| earlier.distance(hash) <= max_distance)
+            })
+        })
+        .collect()
+}
+
+async fn hash(response: &mut APIResponse) -> Option<PerceptualHash> {
+    let data = response.data().await.ok()?;
+    if let Value::Object(content) = &mut response.content {
+        content
+            .entry("image_data")
+            .or_insert_with(|| BASE64_STANDARD.encode(&data).into());
+    }
+
+    let image = image::load_from_memory(&data).ok()?;
+    Some(PerceptualHash::new(&image))
+}
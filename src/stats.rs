@@ -0,0 +1,73 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const LATENCY_SAMPLES: usize = 1000;
+
+#[derive(Debug, Clone, Default)]
+pub struct EndpointStats {
+    pub requests: u64,
+    pub errors: u64,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Record {
+    requests: u64,
+    errors: u64,
+    latencies: VecDeque<Duration>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Stats {
+    records: Arc<Mutex<HashMap<String, Record>>>,
+}
+
+impl Stats {
+    pub fn record(&self, endpoint: &str, latency: Duration, failed: bool) {
+        let name = if endpoint.is_empty() {
+            "default"
+        } else {
+            endpoint
+        };
+        let mut records = self.records.lock().unwrap();
+        let record = records.entry(name.to_string()).or_default();
+
+        record.requests += 1;
+        if failed {
+            record.errors += 1;
+        }
+        if record.latencies.len() == LATENCY_SAMPLES {
+            record.latencies.pop_front();
+        }
+        record.latencies.push_back(latency);
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, EndpointStats> {
+        let records = self.records.lock().unwrap();
+        records
+            .iter()
+            .map(|(name, record)| {
+                let mut latencies: Vec<_> = record.latencies.iter().copied().collect();
+                latencies.sort();
+                let percentile = |p: usize| {
+                    latencies
+                        .get((latencies.len() * p / 100).min(latencies.len().saturating_sub(1)))
+                        .copied()
+                        .unwrap_or_default()
+                };
+
+                let stats = EndpointStats {
+                    requests: record.requests,
+                    errors: record.errors,
+                    p50: percentile(50),
+                    p90: percentile(90),
+                    p99: percentile(99),
+                };
+                (name.clone(), stats)
+            })
+            .collect()
+    }
+}
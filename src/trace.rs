@@ -0,0 +1,13 @@
+use std::fmt::Write;
+
+pub(crate) fn generate_traceparent() -> String {
+    let mut bytes = [0u8; 24];
+    getrandom::getrandom(&mut bytes).expect("cannot generate trace identifiers");
+
+    let hex = bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    });
+
+    format!("00-{}-{}-01", &hex[..32], &hex[32..])
+}
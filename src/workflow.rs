@@ -0,0 +1,344 @@
+use crate::{
+    join_in_order, ImagePig, ImagePigError, OutpaintOptions, Params, Pipeline, PipelineOutput,
+    PreparedImage, Proportion, Request, UpscalingFactor,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+type Extra = Option<serde_json::Map<String, Value>>;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "endpoint", rename_all = "lowercase")]
+enum StepSpec {
+    Default {
+        prompt: String,
+        negative_prompt: Option<String>,
+        params: Extra,
+    },
+    Xl {
+        prompt: String,
+        negative_prompt: Option<String>,
+        params: Extra,
+    },
+    Flux {
+        prompt: String,
+        proportion: Option<Proportion>,
+        params: Extra,
+    },
+    Faceswap {
+        source: String,
+        image: Option<String>,
+        params: Extra,
+    },
+    Upscale {
+        image: Option<String>,
+        factor: Option<u8>,
+        params: Extra,
+    },
+    Cutout {
+        image: Option<String>,
+        params: Extra,
+    },
+    Replace {
+        image: Option<String>,
+        select_prompt: String,
+        prompt: String,
+        negative_prompt: Option<String>,
+        params: Extra,
+    },
+    Outpaint {
+        image: Option<String>,
+        prompt: String,
+        negative_prompt: Option<String>,
+        #[serde(default)]
+        top: u32,
+        #[serde(default)]
+        right: u32,
+        #[serde(default)]
+        bottom: u32,
+        #[serde(default)]
+        left: u32,
+        params: Extra,
+    },
+}
+
+// A JSON job description. Every string may use {input} and {index}, which are
+// replaced per entry of `inputs`; each input runs the steps as its own pipeline.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Workflow {
+    #[serde(default)]
+    inputs: Vec<String>,
+    steps: Vec<StepSpec>,
+    output: Option<String>,
+}
+
+impl Workflow {
+    pub fn from_json(json: &str) -> Result<Self, ImagePigError> {
+        let workflow: Self = serde_json::from_str(json).map_err(ImagePigError::invalid_workflow)?;
+        if workflow.steps.is_empty() {
+            return Err(ImagePigError::invalid_workflow("workflow has no steps"));
+        }
+        Ok(workflow)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, ImagePigError> {
+        let json = std::fs::read_to_string(path).map_err(|e| ImagePigError::from_io(path, e))?;
+        Self::from_json(&json)
+    }
+
+    pub async fn run(&self, imagepig: &ImagePig) -> Vec<Result<PipelineOutput, ImagePigError>> {
+        let inputs = if self.inputs.is_empty() {
+            vec![String::new()]
+        } else {
+            self.inputs.clone()
+        };
+
+        join_in_order(inputs.into_iter().enumerate().map(|(index, input)| {
+            let imagepig = imagepig.clone();
+            let workflow = self.clone();
+            async move {
+                let vars = Vars {
+                    input,
+                    index: index.to_string(),
+                };
+                workflow.run_one(&imagepig, &vars).await
+            }
+        }))
+        .await
+    }
+
+    async fn run_one(
+        &self,
+        imagepig: &ImagePig,
+        vars: &Vars,
+    ) -> Result<PipelineOutput, ImagePigError> {
+        let mut steps = self.steps.iter();
+        let first = steps
+            .next()
+            .map(|step| first_request(step, vars))
+            .transpose()?;
+        let mut pipeline = Pipeline::new(first.ok_or(ImagePigError::MissingData)?);
+
+        for step in steps {
+            pipeline = chain(pipeline, step, vars)?;
+        }
+
+        let result = pipeline.run(imagepig).await?;
+
+        if let Some(output) = &self.output {
+            result.output.save(&vars.apply(output)).await?;
+        }
+
+        Ok(result)
+    }
+}
+
+struct Vars {
+    input: String,
+    index: String,
+}
+
+impl Vars {
+    fn apply(&self, template: &str) -> String {
+        template
+            .replace("{input}", &self.input)
+            .replace("{index}", &self.index)
+    }
+}
+
+fn params(extra: &Extra, vars: &Vars) -> Result<Option<Params>, ImagePigError> {
+    extra
+        .as_ref()
+        .map(|map| {
+            let map: serde_json::Map<String, Value> = map
+                .iter()
+                .map(|(key, value)| match value {
+                    Value::String(text) => (key.clone(), Value::from(vars.apply(text))),
+                    _ => (key.clone(), value.clone()),
+                })
+                .collect();
+            Params::from_serialize(&map)
+        })
+        .transpose()
+}
+
+fn factor(factor: Option<u8>) -> Result<Option<UpscalingFactor>, ImagePigError> {
+    match factor {
+        None => Ok(None),
+        Some(2) => Ok(Some(UpscalingFactor::Two)),
+        Some(4) => Ok(Some(UpscalingFactor::Four)),
+        Some(8) => Ok(Some(UpscalingFactor::Eight)),
+        Some(other) => Err(ImagePigError::invalid_workflow(format!(
+            "unsupported upscaling factor {}",
+            other
+        ))),
+    }
+}
+
+fn outpaint_options(
+    negative_prompt: &Option<String>,
+    sides: [u32; 4],
+    vars: &Vars,
+) -> OutpaintOptions {
+    let [top, right, bottom, left] = sides;
+    let options = OutpaintOptions::new()
+        .top(top)
+        .right(right)
+        .bottom(bottom)
+        .left(left);
+
+    match negative_prompt {
+        Some(negative_prompt) => options.negative_prompt(&vars.apply(negative_prompt)),
+        None => options,
+    }
+}
+
+fn image(image: &Option<String>, vars: &Vars) -> Result<PreparedImage, ImagePigError> {
+    match image {
+        Some(image) => PreparedImage::new(vars.apply(image).as_str()),
+        None => Err(ImagePigError::invalid_workflow(
+            "the first editing step needs an image",
+        )),
+    }
+}
+
+fn first_request(step: &StepSpec, vars: &Vars) -> Result<Request, ImagePigError> {
+    let negative =
+        |negative_prompt: &Option<String>| negative_prompt.as_ref().map(|n| vars.apply(n));
+
+    Ok(match step {
+        StepSpec::Default {
+            prompt,
+            negative_prompt,
+            params: extra,
+        } => Request::default(
+            &vars.apply(prompt),
+            negative(negative_prompt).as_deref(),
+            params(extra, vars)?,
+        ),
+        StepSpec::Xl {
+            prompt,
+            negative_prompt,
+            params: extra,
+        } => Request::xl(
+            &vars.apply(prompt),
+            negative(negative_prompt).as_deref(),
+            params(extra, vars)?,
+        ),
+        StepSpec::Flux {
+            prompt,
+            proportion,
+            params: extra,
+        } => Request::flux(&vars.apply(prompt), *proportion, params(extra, vars)?),
+        StepSpec::Faceswap {
+            source,
+            image: target,
+            params: extra,
+        } => Request::faceswap(
+            PreparedImage::new(vars.apply(source).as_str())?,
+            image(target, vars)?,
+            params(extra, vars)?,
+        )?,
+        StepSpec::Upscale {
+            image: input,
+            factor: upscaling,
+            params: extra,
+        } => Request::upscale(
+            image(input, vars)?,
+            factor(*upscaling)?,
+            params(extra, vars)?,
+        )?,
+        StepSpec::Cutout {
+            image: input,
+            params: extra,
+        } => Request::cutout(image(input, vars)?, params(extra, vars)?)?,
+        StepSpec::Replace {
+            image: input,
+            select_prompt,
+            prompt,
+            negative_prompt,
+            params: extra,
+        } => Request::replace(
+            image(input, vars)?,
+            &vars.apply(select_prompt),
+            &vars.apply(prompt),
+            negative(negative_prompt).as_deref(),
+            params(extra, vars)?,
+        )?,
+        StepSpec::Outpaint {
+            image: input,
+            prompt,
+            negative_prompt,
+            top,
+            right,
+            bottom,
+            left,
+            params: extra,
+        } => Request::outpaint(
+            image(input, vars)?,
+            &vars.apply(prompt),
+            outpaint_options(negative_prompt, [*top, *right, *bottom, *left], vars),
+            params(extra, vars)?,
+        )?,
+    })
+}
+
+fn chain(pipeline: Pipeline, step: &StepSpec, vars: &Vars) -> Result<Pipeline, ImagePigError> {
+    Ok(match step {
+        // later steps edit the previous output, an image of their own would be ignored
+        StepSpec::Faceswap { image: Some(_), .. }
+        | StepSpec::Upscale { image: Some(_), .. }
+        | StepSpec::Cutout { image: Some(_), .. }
+        | StepSpec::Replace { image: Some(_), .. }
+        | StepSpec::Outpaint { image: Some(_), .. } => {
+            return Err(ImagePigError::invalid_workflow(
+                "only the first step takes an image, later steps edit the previous output",
+            ))
+        }
+        StepSpec::Faceswap {
+            source,
+            params: extra,
+            ..
+        } => pipeline.faceswap(
+            PreparedImage::new(vars.apply(source).as_str())?,
+            params(extra, vars)?,
+        ),
+        StepSpec::Upscale {
+            factor: upscaling,
+            params: extra,
+            ..
+        } => pipeline.upscale(factor(*upscaling)?, params(extra, vars)?),
+        StepSpec::Cutout { params: extra, .. } => pipeline.cutout(params(extra, vars)?),
+        StepSpec::Replace {
+            select_prompt,
+            prompt,
+            negative_prompt,
+            params: extra,
+            ..
+        } => pipeline.replace(
+            &vars.apply(select_prompt),
+            &vars.apply(prompt),
+            negative_prompt.as_ref().map(|n| vars.apply(n)).as_deref(),
+            params(extra, vars)?,
+        ),
+        StepSpec::Outpaint {
+            prompt,
+            negative_prompt,
+            top,
+            right,
+            bottom,
+            left,
+            params: extra,
+            ..
+        } => pipeline.outpaint(
+            &vars.apply(prompt),
+            outpaint_options(negative_prompt, [*top, *right, *bottom, *left], vars),
+            params(extra, vars)?,
+        ),
+        StepSpec::Default { .. } | StepSpec::Xl { .. } | StepSpec::Flux { .. } => {
+            return Err(ImagePigError::invalid_workflow(
+                "text-to-image endpoints can only be the first step",
+            ))
+        }
+    })
+}
@@ -1,4 +1,6 @@
-use imagepig::ImagePig;
+#![cfg(feature = "download")]
+
+use imagepig::{ImagePig, OutpaintOptions, SaveAs};
 use std::env;
 use std::fs;
 
@@ -11,54 +13,46 @@ async fn test_api() {
     let mona_lisa = "https://imagepig.com/static/mona-lisa.jpeg";
     let api_key = env::var("IMAGEPIG_API_KEY").unwrap();
 
-    let imagepig = ImagePig::new(api_key, None);
+    let imagepig = ImagePig::new(api_key, None).unwrap();
 
     imagepig
         .default("pig", None, None)
+        .save_as("output/pig1.jpeg")
         .await
-        .unwrap()
-        .save("output/pig1.jpeg")
-        .await;
+        .unwrap();
     imagepig
         .xl("pig", None, None)
+        .save_as("output/pig2.jpeg")
         .await
-        .unwrap()
-        .save("output/pig2.jpeg")
-        .await;
+        .unwrap();
     imagepig
         .flux("pig", None, None)
+        .save_as("output/pig3.jpeg")
         .await
-        .unwrap()
-        .save("output/pig3.jpeg")
-        .await;
+        .unwrap();
     imagepig
         .faceswap(jane, mona_lisa, None)
+        .save_as("output/faceswap.jpeg")
         .await
-        .unwrap()
-        .save("output/faceswap.jpeg")
-        .await;
+        .unwrap();
     imagepig
         .upscale(jane, None, None)
+        .save_as("output/upscale.jpeg")
         .await
-        .unwrap()
-        .save("output/upscale.jpeg")
-        .await;
+        .unwrap();
     imagepig
         .cutout(jane, None)
+        .save_as("output/cutout.png")
         .await
-        .unwrap()
-        .save("output/cutout.png")
-        .await;
+        .unwrap();
     imagepig
         .replace(jane, "woman", "robot", None, None)
+        .save_as("output/replace.jpeg")
         .await
-        .unwrap()
-        .save("output/replace.jpeg")
-        .await;
+        .unwrap();
     imagepig
-        .outpaint(jane, "dress", None, None, Some(500), None, None, None)
+        .outpaint_with(jane, "dress", OutpaintOptions::new().bottom(500), None)
+        .save_as("output/outpaint.jpeg")
         .await
-        .unwrap()
-        .save("output/outpaint.jpeg")
-        .await;
+        .unwrap();
 }
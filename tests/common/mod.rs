@@ -0,0 +1,164 @@
+// a minimal HTTP server standing in for the API, answering with canned replies in order
+#![allow(dead_code)]
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Debug, Clone)]
+pub struct Reply {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub delay: Duration,
+    pub stall: Duration,
+}
+
+impl Reply {
+    pub fn json(status: u16, body: Value) -> Self {
+        Self::bytes(status, "application/json", body.to_string().as_bytes())
+    }
+
+    pub fn bytes(status: u16, content_type: &str, body: &[u8]) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body: body.to_vec(),
+            delay: Duration::ZERO,
+            stall: Duration::ZERO,
+        }
+    }
+
+    pub fn redirect(location: &str) -> Self {
+        Self::bytes(302, "text/plain", b"").header("Location", location)
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn without_header(mut self, name: &str) -> Self {
+        self.headers
+            .retain(|(header, _)| !header.eq_ignore_ascii_case(name));
+        self
+    }
+
+    // waits before answering at all
+    pub fn delayed(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    // sends the headers and half of the body, then waits before the rest
+    pub fn stalled(mut self, stall: Duration) -> Self {
+        self.stall = stall;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Recorded {
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Value,
+}
+
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+}
+
+impl MockServer {
+    // the last reply is repeated once the others are used up
+    pub async fn start(replies: Vec<Reply>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            let mut index = 0;
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let reply = replies[index.min(replies.len() - 1)].clone();
+                index += 1;
+                tokio::spawn(handle(stream, reply, recorded.clone()));
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn handle(mut stream: TcpStream, reply: Reply, requests: Arc<Mutex<Vec<Recorded>>>) {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 8192];
+
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await.unwrap();
+        if read == 0 {
+            return;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let path = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .unwrap_or_default()
+        .to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(": "))
+        .map(|(name, value)| (name.to_lowercase(), value.to_string()))
+        .collect();
+
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or_default();
+    while buffer.len() < header_end + length {
+        let read = stream.read(&mut chunk).await.unwrap();
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    requests.lock().unwrap().push(Recorded {
+        path,
+        headers,
+        body: serde_json::from_slice(&buffer[header_end..]).unwrap_or(Value::Null),
+    });
+
+    tokio::time::sleep(reply.delay).await;
+    let mut head = format!("HTTP/1.1 {} Mock\r\n", reply.status);
+    for (name, value) in &reply.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        reply.body.len()
+    ));
+
+    let (first, rest) = reply.body.split_at(reply.body.len() / 2);
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(first).await;
+    if !reply.stall.is_zero() {
+        let _ = stream.flush().await;
+        tokio::time::sleep(reply.stall).await;
+    }
+    let _ = stream.write_all(rest).await;
+}
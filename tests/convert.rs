@@ -0,0 +1,42 @@
+#![cfg(all(feature = "image", feature = "download"))]
+
+use base64::Engine;
+use image::{DynamicImage, GenericImageView, RgbImage};
+use imagepig::{APIResponse, ImagePigError, OutputFormat};
+
+fn png_response() -> APIResponse {
+    let mut png = std::io::Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(RgbImage::new(8, 4))
+        .write_to(&mut png, image::ImageFormat::Png)
+        .unwrap();
+    let data = base64::prelude::BASE64_STANDARD.encode(png.into_inner());
+    serde_json::from_value(serde_json::json!({"content": {"image_data": data}})).unwrap()
+}
+
+#[tokio::test]
+async fn test_save_as_format() {
+    let response = png_response();
+    let path = std::env::temp_dir().join("imagepig-converted.webp");
+    let path = path.to_str().unwrap();
+
+    response
+        .save_as_format(path, OutputFormat::Webp)
+        .await
+        .unwrap();
+    let converted = image::open(path).unwrap();
+    assert_eq!(converted.dimensions(), (8, 4));
+    assert_eq!(
+        image::ImageFormat::from_path(path).unwrap(),
+        image::ImageFormat::WebP
+    );
+    std::fs::remove_file(path).unwrap();
+
+    let path = std::env::temp_dir().join("imagepig-converted.avif");
+    assert!(matches!(
+        response
+            .save_as_format(path.to_str().unwrap(), OutputFormat::Avif)
+            .await,
+        Err(ImagePigError::UnsupportedFormat(format)) if format == "avif"
+    ));
+    assert!(!path.exists());
+}
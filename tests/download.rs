@@ -0,0 +1,205 @@
+#![cfg(feature = "download")]
+
+mod common;
+
+use common::{MockServer, Reply};
+use imagepig::{ImagePig, ImagePigError, RedirectPolicy, Transfer};
+use serde_json::json;
+use std::time::Duration;
+
+// an API reply pointing at an image hosted on the given server
+fn hosted(cdn: &MockServer, mime_type: &str) -> Reply {
+    Reply::json(
+        200,
+        json!({"image_url": format!("{}/pig.png", cdn.url), "mime_type": mime_type}),
+    )
+}
+
+fn client(api: &MockServer) -> ImagePig {
+    ImagePig::new("key".to_string(), Some(api.url.clone())).unwrap()
+}
+
+#[tokio::test]
+async fn test_download_timeout() {
+    let cdn = MockServer::start(vec![
+        Reply::bytes(200, "image/png", b"hello world").stalled(Duration::from_secs(2))
+    ])
+    .await;
+    let api = MockServer::start(vec![hosted(&cdn, "image/png")]).await;
+    let timeout = Duration::from_millis(200);
+    let imagepig = client(&api).download_timeout(timeout);
+    let response = imagepig.xl("pig", None, None).await.unwrap();
+
+    assert!(matches!(
+        response.data().await,
+        Err(ImagePigError::DownloadTimeout(elapsed)) if elapsed == timeout
+    ));
+
+    // the partially written file is not left behind
+    let path = std::env::temp_dir().join("imagepig-download-timeout.png");
+    assert!(matches!(
+        response.save(path.to_str().unwrap()).await,
+        Err(ImagePigError::DownloadTimeout(_))
+    ));
+    assert!(!path.exists());
+    assert_eq!(cdn.requests().len(), 2);
+}
+
+// the full source chain, as reqwest keeps the redirect policy's reason in a source
+fn describe(error: &ImagePigError) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(current) = source {
+        message.push_str(&format!(": {}", current));
+        source = current.source();
+    }
+    message
+}
+
+#[tokio::test]
+async fn test_download_redirects() {
+    let image = || Reply::bytes(200, "image/png", b"hello");
+
+    // another port is another origin
+    let other = MockServer::start(vec![image()]).await;
+    let cdn = MockServer::start(vec![Reply::redirect(&format!("{}/pig.png", other.url))]).await;
+    let api = MockServer::start(vec![hosted(&cdn, "image/png")]).await;
+    let response = client(&api)
+        .download_redirects(RedirectPolicy::new().cross_origin(false))
+        .xl("pig", None, None)
+        .await
+        .unwrap();
+    let error = response.data().await.unwrap_err();
+    assert!(
+        describe(&error).contains("cross-origin redirect"),
+        "{}",
+        describe(&error)
+    );
+    assert!(other.requests().is_empty());
+    assert_eq!(cdn.requests().len(), 1);
+
+    // followed when cross-origin redirects are allowed, as by default
+    let response = client(&api).xl("pig", None, None).await.unwrap();
+    assert_eq!(response.data().await.unwrap(), b"hello");
+
+    let cdn = MockServer::start(vec![
+        Reply::redirect("/first"),
+        Reply::redirect("/second"),
+        image(),
+    ])
+    .await;
+    let api = MockServer::start(vec![hosted(&cdn, "image/png")]).await;
+    let response = client(&api)
+        .download_redirects(RedirectPolicy::new().max_redirects(1))
+        .xl("pig", None, None)
+        .await
+        .unwrap();
+    let error = response.data().await.unwrap_err();
+    assert!(
+        describe(&error).contains("too many redirects"),
+        "{}",
+        describe(&error)
+    );
+    assert!(cdn
+        .requests()
+        .iter()
+        .all(|request| request.path != "/second"));
+}
+
+#[tokio::test]
+async fn test_download_hosts() {
+    let cdn = MockServer::start(vec![Reply::bytes(200, "image/png", b"hello")]).await;
+    let api = MockServer::start(vec![hosted(&cdn, "image/png")]).await;
+
+    let response = client(&api)
+        .download_redirects(RedirectPolicy::new().allow_host("imagepig.com"))
+        .xl("pig", None, None)
+        .await
+        .unwrap();
+    assert!(matches!(
+        response.data().await,
+        Err(ImagePigError::ForbiddenHost(url)) if url == format!("{}/pig.png", cdn.url)
+    ));
+    assert!(cdn.requests().is_empty());
+
+    let response = client(&api)
+        .download_redirects(RedirectPolicy::new().allow_host("127.0.0.1"))
+        .xl("pig", None, None)
+        .await
+        .unwrap();
+    assert_eq!(response.data().await.unwrap(), b"hello");
+}
+
+#[tokio::test]
+async fn test_download_content_type() {
+    let fetch = |image: Reply, verify: bool| async move {
+        let cdn = MockServer::start(vec![image]).await;
+        let api = MockServer::start(vec![hosted(&cdn, "image/png")]).await;
+        client(&api)
+            .verify_content_type(verify)
+            .xl("pig", None, None)
+            .await
+            .unwrap()
+            .data()
+            .await
+    };
+    let jpeg = || Reply::bytes(200, "image/jpeg", b"hello");
+
+    assert!(matches!(
+        fetch(jpeg(), true).await,
+        Err(ImagePigError::ContentTypeMismatch { expected, actual })
+            if expected == "image/png" && actual == "image/jpeg"
+    ));
+    // not checked unless enabled
+    assert!(fetch(jpeg(), false).await.is_ok());
+
+    // parameters and case are ignored, a missing header cannot be checked
+    let png = Reply::bytes(200, "Image/PNG; charset=binary", b"hello");
+    assert!(fetch(png, true).await.is_ok());
+    let unlabeled = jpeg().without_header("Content-Type");
+    assert!(fetch(unlabeled, true).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_transfer_progress() {
+    let image = vec![7u8; 100_000];
+    let cdn = MockServer::start(vec![
+        Reply::bytes(200, "image/png", &image).stalled(Duration::from_millis(50))
+    ])
+    .await;
+    let api = MockServer::start(vec![hosted(&cdn, "image/png")]).await;
+
+    let reports = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = reports.clone();
+    let imagepig = client(&api).on_progress(move |progress| {
+        recorded.lock().unwrap().push(progress);
+    });
+
+    // the base64 payload spans several upload chunks
+    let response = imagepig.cutout(image.clone(), None).await.unwrap();
+    assert_eq!(response.data().await.unwrap(), image);
+
+    let reports = reports.lock().unwrap();
+    for transfer in [Transfer::Upload, Transfer::Download] {
+        let reports: Vec<_> = reports
+            .iter()
+            .filter(|progress| progress.transfer == transfer)
+            .collect();
+        assert!(reports.len() > 1, "{:?}", transfer);
+        assert!(reports.windows(2).all(|pair| pair[0].bytes < pair[1].bytes));
+
+        let last = reports.last().unwrap();
+        assert_eq!(last.total, Some(last.bytes), "{:?}", transfer);
+    }
+
+    let uploaded = reports
+        .iter()
+        .rfind(|progress| progress.transfer == Transfer::Upload)
+        .unwrap();
+    let sent: u64 = api.requests()[0].headers["content-length"].parse().unwrap();
+    assert_eq!(uploaded.bytes, sent);
+    assert_eq!(
+        api.requests()[0].body["image_data"].as_str().unwrap().len(),
+        133_336
+    );
+}
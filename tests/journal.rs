@@ -0,0 +1,59 @@
+mod common;
+
+use common::{MockServer, Reply};
+use imagepig::{ImagePig, ImagePigError};
+use serde_json::json;
+
+fn journal_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("imagepig-journal-{}.jsonl", name));
+    let _ = std::fs::remove_file(&path);
+    path.to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_replay_failures() {
+    let path = journal_path("replay");
+    let server = MockServer::start(vec![
+        Reply::json(500, json!({"message": "internal error"})),
+        Reply::json(200, json!({"image_data": "aGVsbG8=", "seed": 7})),
+    ])
+    .await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone()))
+        .unwrap()
+        .journal(path.clone());
+
+    let result = imagepig.xl("pig", None, None).await;
+    assert!(matches!(
+        result,
+        Err(ImagePigError::Api { status: 500, .. })
+    ));
+
+    let journal = std::fs::read_to_string(&path).unwrap();
+    let entry: serde_json::Value = serde_json::from_str(journal.trim()).unwrap();
+    assert_eq!(entry["endpoint"], "xl");
+    assert_eq!(entry["payload"]["positive_prompt"], "pig");
+
+    // replaying truncates the journal, successful requests are not recorded again
+    let results = imagepig.replay_failures().await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].as_ref().unwrap().seed(), Some(7));
+    assert_eq!(server.requests()[1].body["positive_prompt"], "pig");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+
+    assert!(imagepig.replay_failures().await.unwrap().is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_journal_write_failure_keeps_request_error() {
+    let server = MockServer::start(vec![Reply::json(503, json!({"message": "busy"}))]).await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone()))
+        .unwrap()
+        .journal("/nonexistent-directory/journal.jsonl".to_string());
+
+    let result = imagepig.xl("pig", None, None).await;
+    assert!(matches!(
+        result,
+        Err(ImagePigError::Api { status: 503, .. })
+    ));
+}
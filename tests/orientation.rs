@@ -0,0 +1,38 @@
+#![cfg(all(feature = "image", feature = "download"))]
+
+use base64::Engine;
+use image::{DynamicImage, GenericImageView, RgbImage};
+use imagepig::APIResponse;
+
+// a JPEG with an APP1 segment carrying EXIF orientation 6 (rotate 90° clockwise)
+fn rotated_jpeg(width: u32, height: u32) -> Vec<u8> {
+    let mut jpeg = std::io::Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(RgbImage::new(width, height))
+        .write_to(&mut jpeg, image::ImageFormat::Jpeg)
+        .unwrap();
+    let jpeg = jpeg.into_inner();
+
+    let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08".to_vec();
+    exif.extend_from_slice(&[0, 1, 0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, 0, 0, 0, 0]);
+
+    let mut data = jpeg[..2].to_vec();
+    data.extend_from_slice(&[0xff, 0xe1]);
+    data.extend_from_slice(&((exif.len() + 2) as u16).to_be_bytes());
+    data.extend_from_slice(&exif);
+    data.extend_from_slice(&jpeg[2..]);
+    data
+}
+
+#[tokio::test]
+async fn test_save_oriented() {
+    let data = base64::prelude::BASE64_STANDARD.encode(rotated_jpeg(40, 20));
+    let response: APIResponse =
+        serde_json::from_value(serde_json::json!({"content": {"image_data": data}})).unwrap();
+
+    let path = std::env::temp_dir().join("imagepig-oriented.jpeg");
+    let path = path.to_str().unwrap();
+    response.save_oriented(path).await.unwrap();
+
+    assert_eq!(image::open(path).unwrap().dimensions(), (20, 40));
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,41 @@
+use imagepig::{ImagePigError, Prompt};
+
+#[test]
+fn test_weighted_prompt() {
+    let prompt = Prompt::new()
+        .text("cute piglet")
+        .term("pig", 1.3)
+        .unwrap()
+        .term("mud", 0.7)
+        .unwrap();
+
+    assert_eq!(prompt.to_string(), "cute piglet, (pig:1.3), (mud:0.7)");
+}
+
+#[test]
+fn test_invalid_weights() {
+    for weight in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, -0.5] {
+        assert!(matches!(
+            Prompt::new().term("pig", weight),
+            Err(ImagePigError::InvalidWeight(_))
+        ));
+    }
+    assert_eq!(
+        Prompt::new().term("pig", 0.0).unwrap().to_string(),
+        "(pig:0)"
+    );
+}
+
+#[test]
+fn test_truncate_prompt() {
+    let prompt = Prompt::new()
+        .text("cute piglet running on a green garden")
+        .term("pig", 1.3)
+        .unwrap();
+
+    assert!(prompt.validate(20).is_err());
+
+    let truncated = prompt.truncate(30);
+    assert_eq!(truncated.to_string(), "cute piglet running, (pig:1.3)");
+    assert!(truncated.validate(30).is_ok());
+}
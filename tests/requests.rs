@@ -0,0 +1,305 @@
+mod common;
+
+use common::{MockServer, Reply};
+use imagepig::{ErrorKind, ImagePig, ImagePigError, Proportion, Request, UpscalingFactor};
+use serde_json::json;
+
+fn ok() -> Reply {
+    Reply::json(200, json!({"image_data": "aGVsbG8="}))
+}
+
+#[tokio::test]
+async fn test_client_defaults() {
+    let server = MockServer::start(vec![ok()]).await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone()))
+        .unwrap()
+        .default_proportion(Proportion::Square)
+        .default_upscaling_factor(UpscalingFactor::Four);
+
+    imagepig.flux("pig", None, None).await.unwrap();
+    imagepig
+        .flux("pig", Some(Proportion::Wide), None)
+        .await
+        .unwrap();
+    imagepig
+        .upscale("https://example.com/pig.jpeg", None, None)
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].path, "/flux");
+    assert_eq!(requests[0].body["proportion"], "square");
+    assert_eq!(requests[1].body["proportion"], "wide");
+    assert_eq!(requests[2].body["upscaling_factor"], 4);
+}
+
+#[tokio::test]
+async fn test_gather_deduplicates_requests() {
+    let server = MockServer::start(vec![ok()]).await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+
+    let results = imagepig
+        .gather([
+            Request::flux("pig", None, None),
+            Request::flux("cow", None, None),
+            // identical once the default proportion is applied
+            Request::flux("pig", Some(Proportion::Landscape), None),
+        ])
+        .await;
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|result| result.is_ok()));
+    assert_eq!(server.requests().len(), 2);
+
+    let imagepig = imagepig.deduplicate_requests(false);
+    imagepig
+        .gather([
+            Request::flux("pig", None, None),
+            Request::flux("pig", None, None),
+        ])
+        .await;
+    assert_eq!(server.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn test_gather_shares_errors_between_duplicates() {
+    let server = MockServer::start(vec![
+        Reply::json(400, json!({"message": "bad prompt"})),
+        ok(),
+    ])
+    .await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+
+    let results = imagepig
+        .gather([
+            Request::xl("pig", None, None),
+            Request::xl("pig", None, None),
+        ])
+        .await;
+    for result in &results {
+        let error = result.as_ref().unwrap_err();
+        assert!(matches!(error, ImagePigError::Shared(_)));
+        assert_eq!(error.to_string(), "API returned 400: bad prompt");
+    }
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn test_shared_errors_keep_their_shape() {
+    let server = MockServer::start(vec![Reply::json(
+        200,
+        json!({"error": "Prompt violates content policy"}),
+    )])
+    .await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+
+    let results = imagepig
+        .gather([
+            Request::xl("pig", None, None),
+            Request::xl("pig", None, None),
+            Request::xl("cow", None, None),
+        ])
+        .await;
+    let errors: Vec<_> = results.iter().map(|r| r.as_ref().unwrap_err()).collect();
+
+    for error in &errors[..2] {
+        assert!(matches!(error, ImagePigError::Shared(_)));
+        assert!(error.is_soft());
+        assert!(matches!(
+            error.inner(),
+            ImagePigError::Api { status: 200, message, .. } if message == "Prompt violates content policy"
+        ));
+    }
+
+    // a failure only one request receives is not wrapped
+    assert!(matches!(errors[2], ImagePigError::Api { status: 200, .. }));
+    assert!(errors[2].is_soft());
+    assert!(std::ptr::eq(errors[2].inner(), errors[2]));
+}
+
+#[tokio::test]
+async fn test_classification_ignores_url() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+
+    let imagepig = ImagePig::new(
+        "key".to_string(),
+        Some(format!("http://{}/ssl-tls-gateway/", address)),
+    )
+    .unwrap();
+
+    let error = imagepig.xl("pig", None, None).await.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Connection);
+}
+
+#[tokio::test]
+async fn test_gateway_prefix() {
+    let server = MockServer::start(vec![ok()]).await;
+    let imagepig =
+        ImagePig::new("key".to_string(), Some(format!("{}/imagepig", server.url))).unwrap();
+
+    imagepig.xl("pig", None, None).await.unwrap();
+    let result = imagepig
+        .execute(Request::new("//evil.example/steal", Default::default()))
+        .await;
+    assert!(matches!(result, Err(ImagePigError::InvalidEndpoint(_))));
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "/imagepig/xl");
+}
+
+#[tokio::test]
+async fn test_soft_error_retries() {
+    let overloaded = || Reply::json(200, json!({"error": "Model is temporarily overloaded"}));
+
+    // retried until the image arrives
+    let server = MockServer::start(vec![overloaded(), ok()]).await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+    assert!(imagepig.xl("pig", None, None).await.is_ok());
+    assert_eq!(server.requests().len(), 2);
+
+    // gives up after the configured number of retries
+    let server = MockServer::start(vec![overloaded()]).await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone()))
+        .unwrap()
+        .soft_error_retries(1);
+    let error = imagepig.xl("pig", None, None).await.unwrap_err();
+    assert!(error.is_soft());
+    assert_eq!(server.requests().len(), 2);
+
+    // errors that are not temporary are returned right away
+    let server = MockServer::start(vec![
+        Reply::json(200, json!({"error": "Prompt violates content policy"})),
+        ok(),
+    ])
+    .await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+    let error = imagepig.xl("pig", None, None).await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "API returned 200: Prompt violates content policy"
+    );
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn test_endpoint_timeouts() {
+    let server = MockServer::start(vec![ok().delayed(std::time::Duration::from_secs(1))]).await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone()))
+        .unwrap()
+        .endpoint_timeout("xl", std::time::Duration::from_millis(100));
+
+    let error = imagepig.xl("pig", None, None).await.unwrap_err();
+    assert!(matches!(error, ImagePigError::Timeout(_)));
+    assert_eq!(error.kind(), ErrorKind::Timeout);
+
+    // other endpoints keep their built-in timeout
+    assert!(imagepig.flux("pig", None, None).await.is_ok());
+}
+
+#[cfg(feature = "download")]
+#[tokio::test]
+async fn test_pipeline_stops_at_multiple_images() {
+    let server = MockServer::start(vec![Reply::json(
+        200,
+        json!({"images": [{"image_data": "aGVsbG8="}, {"image_data": "d29ybGQ="}]}),
+    )])
+    .await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+
+    let result = imagepig::Pipeline::new(Request::flux("pig", None, None))
+        .upscale(None, None)
+        .run(&imagepig)
+        .await;
+    assert!(matches!(result, Err(ImagePigError::MultipleImages(2))));
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn test_rate_limit_status() {
+    let server = MockServer::start(vec![
+        ok(),
+        ok().header("X-RateLimit-Limit", "100")
+            .header("X-RateLimit-Remaining", "42")
+            .header("X-RateLimit-Reset", "30"),
+        ok().header("RateLimit-Remaining", "7")
+            .header("RateLimit-Reset", "1900000000"),
+        Reply::json(429, json!({"message": "slow down"})).header("Retry-After", "5"),
+        ok(),
+    ])
+    .await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+    let seconds_from_now = |time: std::time::SystemTime| {
+        time.duration_since(std::time::SystemTime::now())
+            .unwrap()
+            .as_secs_f64()
+    };
+
+    imagepig.xl("pig", None, None).await.unwrap();
+    assert!(imagepig.rate_limit_status().is_none());
+
+    // small reset values are seconds from now
+    imagepig.xl("pig", None, None).await.unwrap();
+    let status = imagepig.rate_limit_status().unwrap();
+    assert_eq!((status.limit, status.remaining), (Some(100), Some(42)));
+    assert!((28.0..=30.0).contains(&seconds_from_now(status.reset_at.unwrap())));
+
+    // the unprefixed headers are read too, and large values are Unix timestamps
+    imagepig.xl("pig", None, None).await.unwrap();
+    let status = imagepig.rate_limit_status().unwrap();
+    assert_eq!((status.limit, status.remaining), (None, Some(7)));
+    assert_eq!(
+        status.reset_at,
+        Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_900_000_000))
+    );
+
+    // Retry-After is observed on the rate-limited response itself
+    let error = imagepig.xl("pig", None, None).await.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::RateLimited);
+    let status = imagepig.rate_limit_status().unwrap();
+    assert_eq!((status.limit, status.remaining), (None, None));
+    assert!((3.0..=5.0).contains(&seconds_from_now(status.reset_at.unwrap())));
+
+    // a response without rate-limit headers keeps the latest status
+    imagepig.xl("pig", None, None).await.unwrap();
+    assert!(imagepig.rate_limit_status().is_some());
+}
+
+#[cfg(feature = "download")]
+#[tokio::test]
+async fn test_manifest_hashes_every_image() {
+    let server = MockServer::start(vec![
+        Reply::json(
+            200,
+            json!({"images": [{"image_data": "aGVsbG8=", "seed": 1}, {"image_data": "d29ybGQ=", "seed": 2}]}),
+        ),
+        Reply::json(400, json!({"message": "bad prompt"})),
+    ])
+    .await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+
+    let (results, manifest) = imagepig
+        .gather_with_manifest([Request::xl("pig", None, None)])
+        .await;
+    assert!(results[0].is_ok());
+
+    let entry = &manifest.entries[0];
+    assert_eq!(entry.error, None);
+    assert_eq!(
+        entry.sha256,
+        [
+            // sha256 of "hello" and "world"
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+            "486ea46224d1bb4fb680f34f7c9ad96a8f24ec88be73ea8e5a6c65260e9cb8a7",
+        ]
+    );
+
+    // failed requests keep the error and have no hashes
+    let (_, manifest) = imagepig
+        .gather_with_manifest([Request::xl("cow", None, None)])
+        .await;
+    let entry = &manifest.entries[0];
+    assert!(entry.sha256.is_empty());
+    assert_eq!(entry.error.as_deref(), Some("API returned 400: bad prompt"));
+}
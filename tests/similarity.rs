@@ -0,0 +1,99 @@
+#![cfg(feature = "image")]
+
+mod common;
+
+use base64::Engine;
+use image::{DynamicImage, GrayImage, Luma};
+use imagepig::{dedup, find_duplicates, ssim, APIResponse, ImagePigError, PerceptualHash};
+
+fn gradient(flip: bool) -> DynamicImage {
+    DynamicImage::ImageLuma8(GrayImage::from_fn(64, 64, |x, _| {
+        Luma([if flip { 255 - x as u8 * 4 } else { x as u8 * 4 }])
+    }))
+}
+
+#[test]
+fn test_similarity() {
+    let image = gradient(false);
+    let flipped = gradient(true);
+
+    assert_eq!(
+        PerceptualHash::new(&image).distance(&PerceptualHash::new(&image)),
+        0
+    );
+    assert!(PerceptualHash::new(&image).distance(&PerceptualHash::new(&flipped)) > 10);
+
+    assert!((ssim(&image, &image) - 1.0).abs() < 1e-9);
+    assert!(ssim(&image, &flipped) < 0.5);
+}
+
+#[test]
+fn test_find_duplicates() {
+    let hashes = [
+        PerceptualHash(0b0000),
+        PerceptualHash(u64::MAX),
+        PerceptualHash(0b0001),
+        PerceptualHash(u64::MAX << 8),
+    ];
+
+    assert_eq!(find_duplicates(&hashes, 0), [None, None, None, None]);
+    assert_eq!(find_duplicates(&hashes, 8), [None, None, Some(0), Some(1)]);
+}
+
+fn png(image: &DynamicImage) -> Vec<u8> {
+    let mut png = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageFormat::Png).unwrap();
+    png.into_inner()
+}
+
+fn inline(data: &[u8]) -> Result<APIResponse, ImagePigError> {
+    let data = base64::prelude::BASE64_STANDARD.encode(data);
+    Ok(serde_json::from_value(serde_json::json!({"content": {"image_data": data}})).unwrap())
+}
+
+#[tokio::test]
+async fn test_dedup_results() {
+    let image = png(&gradient(false));
+    let flipped = png(&gradient(true));
+
+    let mut results = vec![
+        inline(&image),
+        Err(ImagePigError::MissingData),
+        inline(b"not an image"),
+        inline(&flipped),
+        inline(&image),
+        inline(&flipped),
+    ];
+    assert_eq!(
+        dedup(&mut results, 4).await,
+        [None, None, None, None, Some(0), Some(3)]
+    );
+}
+
+#[cfg(feature = "download")]
+#[tokio::test]
+async fn test_dedup_keeps_hosted_images() {
+    use common::{MockServer, Reply};
+
+    let cdn = MockServer::start(vec![Reply::bytes(200, "image/png", &png(&gradient(false)))]).await;
+    let api = MockServer::start(vec![Reply::json(
+        200,
+        serde_json::json!({"image_url": format!("{}/pig.png", cdn.url)}),
+    )])
+    .await;
+    let imagepig = imagepig::ImagePig::new("key".to_string(), Some(api.url.clone())).unwrap();
+
+    let mut results = imagepig
+        .gather([
+            imagepig::Request::xl("pig", None, None),
+            imagepig::Request::xl("piglet", None, None),
+        ])
+        .await;
+    assert_eq!(dedup(&mut results, 4).await, [None, Some(0)]);
+    assert_eq!(cdn.requests().len(), 2);
+
+    // the downloaded image is reused instead of being fetched again
+    let data = results[0].as_ref().unwrap().data().await.unwrap();
+    assert_eq!(image::load_from_memory(&data).unwrap().width(), 64);
+    assert_eq!(cdn.requests().len(), 2);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{MockServer, Reply};
+use imagepig::ImagePig;
+use serde_json::json;
+use std::time::Duration;
+
+fn ok() -> Reply {
+    Reply::json(200, json!({"image_data": "aGVsbG8="}))
+}
+
+#[tokio::test]
+async fn test_endpoint_stats() {
+    // the n-th default request takes n * 20 ms
+    let mut replies: Vec<_> = (0..10)
+        .map(|n| ok().delayed(Duration::from_millis(n * 20)))
+        .collect();
+    replies.push(Reply::json(400, json!({"message": "bad prompt"})));
+    let server = MockServer::start(replies).await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+
+    for _ in 0..10 {
+        imagepig.default("pig", None, None).await.unwrap();
+    }
+    for _ in 0..2 {
+        assert!(imagepig.xl("pig", None, None).await.is_err());
+    }
+
+    let stats = imagepig.stats();
+    assert_eq!(stats.len(), 2);
+
+    // "" is reported as "default"
+    let default = &stats["default"];
+    assert_eq!((default.requests, default.errors), (10, 0));
+    assert!(default.p50 >= Duration::from_millis(100) && default.p50 < Duration::from_millis(180));
+    assert!(default.p90 >= Duration::from_millis(180));
+    assert_eq!(default.p99, default.p90);
+
+    let xl = &stats["xl"];
+    assert_eq!((xl.requests, xl.errors), (2, 2));
+}
+
+#[tokio::test]
+async fn test_endpoint_stats_window() {
+    // only the latest 1000 latencies count, so the slow first request drops out
+    let server = MockServer::start(vec![ok().delayed(Duration::from_millis(500)), ok()]).await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+
+    imagepig.xl("pig", None, None).await.unwrap();
+    assert!(imagepig.stats()["xl"].p99 >= Duration::from_millis(500));
+
+    for _ in 0..1000 {
+        imagepig.xl("pig", None, None).await.unwrap();
+    }
+    let xl = &imagepig.stats()["xl"];
+    assert_eq!(xl.requests, 1001);
+    assert!(xl.p99 < Duration::from_millis(500));
+}
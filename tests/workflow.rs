@@ -0,0 +1,110 @@
+#![cfg(feature = "download")]
+
+mod common;
+
+use common::{MockServer, Reply};
+use imagepig::{ImagePig, ImagePigError, Workflow};
+use serde_json::json;
+
+#[test]
+fn test_parse_workflow() {
+    let workflow = Workflow::from_json(
+        r#"{
+            "inputs": ["pig", "cow"],
+            "steps": [
+                {"endpoint": "flux", "prompt": "cute {input}", "proportion": "square"},
+                {"endpoint": "upscale", "factor": 4},
+                {"endpoint": "cutout", "params": {"format": "png"}}
+            ],
+            "output": "output/{index}-{input}.png"
+        }"#,
+    );
+    assert!(workflow.is_ok());
+
+    for json in [
+        r#"{"steps": []}"#,
+        r#"{"steps": [{"endpoint": "unknown"}]}"#,
+        r#"{"steps": [{"endpoint": "flux"}]}"#,
+    ] {
+        assert!(matches!(
+            Workflow::from_json(json),
+            Err(ImagePigError::InvalidWorkflow(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_run_workflow() {
+    let server = MockServer::start(vec![Reply::json(200, json!({"image_data": "aGVsbG8="}))]).await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+    let dir = std::env::temp_dir();
+    let output = dir.join("imagepig-workflow-{index}-{input}.png");
+
+    let workflow = Workflow::from_json(
+        &json!({
+            "inputs": ["pig", "cow"],
+            "steps": [
+                {"endpoint": "flux", "prompt": "cute {input}", "proportion": "square"},
+                {"endpoint": "cutout", "params": {"label": "{index}-{input}"}},
+            ],
+            "output": output.to_str().unwrap(),
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let results = workflow.run(&imagepig).await;
+    assert_eq!(results.len(), 2);
+    for (index, input) in ["pig", "cow"].iter().enumerate() {
+        let result = results[index].as_ref().unwrap();
+        assert_eq!(result.intermediate.len(), 1);
+
+        let path = dir.join(format!("imagepig-workflow-{}-{}.png", index, input));
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    // the inputs run concurrently, so their requests may arrive in any order
+    let requests = server.requests();
+    let mut prompts: Vec<_> = requests
+        .iter()
+        .filter(|request| request.path == "/flux")
+        .map(|request| request.body["positive_prompt"].as_str().unwrap())
+        .collect();
+    prompts.sort();
+    assert_eq!(prompts, ["cute cow", "cute pig"]);
+
+    let mut labels: Vec<_> = requests
+        .iter()
+        .filter(|request| request.path == "/cutout")
+        .map(|request| {
+            assert_eq!(request.body["image_data"], "aGVsbG8=");
+            request.body["label"].as_str().unwrap()
+        })
+        .collect();
+    labels.sort();
+    assert_eq!(labels, ["0-pig", "1-cow"]);
+}
+
+#[tokio::test]
+async fn test_chained_steps_take_no_image() {
+    let server = MockServer::start(vec![Reply::json(200, json!({"image_data": "aGVsbG8="}))]).await;
+    let imagepig = ImagePig::new("key".to_string(), Some(server.url.clone())).unwrap();
+
+    let workflow = Workflow::from_json(
+        r#"{
+            "steps": [
+                {"endpoint": "flux", "prompt": "pig"},
+                {"endpoint": "upscale", "image": "https://example.com/cow.jpeg"}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let results = workflow.run(&imagepig).await;
+    assert!(matches!(
+        results[..],
+        [Err(ImagePigError::InvalidWorkflow(_))]
+    ));
+    assert!(server.requests().is_empty());
+}